@@ -0,0 +1,58 @@
+"""Benchmark comparing reads on one Python thread with the same reads spread over several.
+
+Needs an installed build, e.g. `maturin develop --release && python benches/concurrent_reads.py`.
+Scaling with the thread count shows that reads run without holding the GIL, see
+tests/test_gil.py for the pass/fail check.
+"""
+import os
+import tempfile
+import threading
+import time
+
+import pysled
+
+THREADS = min(4, os.cpu_count() or 1)
+ROUNDS = 40
+KEYS = 20_000
+
+
+def _scan(db, rounds):
+    for _ in range(rounds):
+        # walks the whole tree inside sled
+        len(db)
+
+
+def _get(db, rounds):
+    for _ in range(rounds):
+        for i in range(0, KEYS, 4):
+            db.get(i.to_bytes(4, "big"))
+
+
+def _timed(read, db, threads, rounds):
+    workers = [threading.Thread(target=read, args=(db, rounds)) for _ in range(threads)]
+    start = time.perf_counter()
+    for worker in workers:
+        worker.start()
+    for worker in workers:
+        worker.join()
+    return time.perf_counter() - start
+
+
+def main():
+    with tempfile.TemporaryDirectory() as path:
+        db = pysled.SledDb(path)
+        db.insert_many((i.to_bytes(4, "big"), b"v" * 64) for i in range(KEYS))
+
+        print(f"{THREADS} threads, {ROUNDS} rounds over {KEYS} keys")
+        for name, read in [("len", _scan), ("get", _get)]:
+            serial = _timed(read, db, 1, ROUNDS)
+            parallel = _timed(read, db, THREADS, ROUNDS // THREADS)
+            print(
+                f"{name}: serial {serial:.3f}s, parallel {parallel:.3f}s, "
+                f"speedup {serial / parallel:.2f}x"
+            )
+        db.close()
+
+
+if __name__ == "__main__":
+    main()
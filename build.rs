@@ -0,0 +1,18 @@
+use std::{env, fs, path::Path};
+
+/// Exposes the resolved sled version and the target triple to the crate, for `build_info`.
+fn main() {
+    let lock = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    let lock = fs::read_to_string(lock).unwrap_or_default();
+    let sled_version = lock
+        .split("[[package]]")
+        .find(|package| package.contains("\nname = \"sled\"\n"))
+        .and_then(|package| package.lines().find_map(|l| l.strip_prefix("version = ")))
+        .map_or("unknown", |version| version.trim_matches('"'));
+    println!("cargo:rustc-env=PYSLED_SLED_VERSION={}", sled_version);
+    println!(
+        "cargo:rustc-env=PYSLED_TARGET={}",
+        env::var("TARGET").unwrap()
+    );
+}
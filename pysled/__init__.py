@@ -0,0 +1,5 @@
+from . import _pysled
+from ._pysled import *  # noqa: F401,F403
+from ._pysled import __all__, __version__, keys  # noqa: F401
+
+__doc__ = _pysled.__doc__
@@ -0,0 +1,284 @@
+import os
+from collections.abc import (
+    Awaitable,
+    Callable,
+    ItemsView,
+    Iterable,
+    Iterator,
+    KeysView,
+    Mapping,
+    MutableMapping,
+    Sequence,
+    ValuesView,
+)
+from typing import Any, Literal, TypeVar, overload
+
+from typing_extensions import Self
+
+from . import keys as keys
+
+_T = TypeVar("_T")
+_K = TypeVar("_K")
+_V = TypeVar("_V")
+
+# Keys and values accept bytes-like objects and, outside of strict mode, str.
+_Bytes = bytes | bytearray | memoryview | SledValue | str | Sequence[int]
+_Path = str | os.PathLike[str]
+# Values are returned as lists of ints, keys as bytes.
+_Entry = tuple[bytes, list[int]]
+_Codec = Literal["bytes", "str", "int", "json", "msgpack", "pickle"] | PickleCodec | Any
+_MergeOperator = Literal["u64_add", "append", "set_union", "max", "min"] | Callable[[bytes, bytes | None, bytes], _Bytes | None]
+
+class SledTree(MutableMapping[bytes, list[int]]):
+    def insert(self, key: _Bytes, value: _Bytes) -> list[int] | None: ...
+    @overload
+    def get(self, key: _Bytes, default: None = None, *, raw: Literal[False] = False) -> list[int] | None: ...
+    @overload
+    def get(self, key: _Bytes, default: _T, *, raw: Literal[False] = False) -> list[int] | _T: ...
+    @overload
+    def get(self, key: _Bytes, default: _T = ..., *, raw: Literal[True]) -> SledValue | _T: ...
+    def remove(self, key: _Bytes) -> list[int] | None: ...
+    def clear(self) -> None: ...
+    def all(self) -> list[_Entry]: ...
+    def range(self, start: _Bytes | None = None, end: _Bytes | None = None, inclusive: bool = False) -> SledIter: ...
+    def scan_prefix(self, prefix: _Bytes) -> SledIter: ...
+    def keys(self) -> SledKeysView[bytes]: ...
+    def values(self) -> SledValuesView[list[int]]: ...
+    def items(self) -> SledItemsView[bytes, list[int]]: ...
+    def first(self) -> _Entry | None: ...
+    def last(self) -> _Entry | None: ...
+    def get_lt(self, key: _Bytes) -> _Entry | None: ...
+    def get_gt(self, key: _Bytes) -> _Entry | None: ...
+    def floor(self, key: _Bytes) -> _Entry | None: ...
+    def ceiling(self, key: _Bytes) -> _Entry | None: ...
+    def pop_min(self) -> _Entry | None: ...
+    def pop_max(self) -> _Entry | None: ...
+    def pop_min_n(self, n: int) -> list[_Entry]: ...
+    def transaction(self, f: Callable[[TransactionalTree], _T]) -> _T: ...
+    def apply_batch(self, batch: SledBatch) -> None: ...
+    def insert_many(self, pairs: Iterable[tuple[_Bytes, _Bytes]]) -> None: ...
+    def set_merge_operator(self, operator: _MergeOperator) -> None: ...
+    def merge(self, key: _Bytes, value: _Bytes) -> list[int] | None: ...
+    def watch_prefix(self, prefix: _Bytes) -> SledSubscriber: ...
+    def compare_and_swap(self, key: _Bytes, old: _Bytes | None, new: _Bytes | None) -> None: ...
+    def update_and_fetch(self, key: _Bytes, f: Callable[[bytes | None], _Bytes | None]) -> list[int] | None: ...
+    def fetch_and_update(self, key: _Bytes, f: Callable[[bytes | None], _Bytes | None]) -> list[int] | None: ...
+    def compare_and_swamp(self, key: _Bytes, old: _Bytes | None, new: _Bytes | None) -> CompareAndSwapError | None: ...
+    def checksum(self) -> int: ...
+    def flush(self) -> int: ...
+    def flush_async(self) -> Awaitable[int]: ...
+    def is_empty(self) -> bool: ...
+    def __len__(self) -> int: ...
+    def __contains__(self, key: object) -> bool: ...
+    def __getitem__(self, key: _Bytes) -> list[int]: ...
+    def __setitem__(self, key: _Bytes, value: _Bytes) -> None: ...
+    def __delitem__(self, key: _Bytes) -> None: ...
+    def __iter__(self) -> SledIter: ...
+    @overload
+    def pop(self, key: _Bytes) -> list[int]: ...
+    @overload
+    def pop(self, key: _Bytes, *default: _T) -> list[int] | _T: ...
+    def popitem(self) -> _Entry: ...
+    def setdefault(self, key: _Bytes, default: _Bytes) -> list[int]: ...  # type: ignore[override]
+    def update(self, other: Mapping[Any, Any] | Iterable[tuple[_Bytes, _Bytes]]) -> None: ...  # type: ignore[override]
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    @property
+    def name(self) -> bytes: ...
+    def contains_key(self, key: _Bytes) -> bool: ...
+    def len(self) -> int: ...
+
+class SledDb(SledTree):
+    def __init__(
+        self,
+        path: _Path,
+        *,
+        cache_capacity: int | None = None,
+        mode: Literal["low_space", "high_throughput"] | None = None,
+        use_compression: bool | None = None,
+        compression_factor: int | None = None,
+    ) -> None: ...
+    @staticmethod
+    def temporary(path: _Path | None = None) -> SledDb: ...
+    @staticmethod
+    def in_memory() -> SledDb: ...
+    def checksum(self) -> int: ...
+    @overload
+    def open_tree(self, name: _Bytes, key_codec: None = None, value_codec: None = None) -> SledTree: ...
+    @overload
+    def open_tree(self, name: _Bytes, key_codec: _Codec | None = None, value_codec: _Codec | None = None) -> SledTypedTree: ...
+    def drop_tree(self, name: _Bytes) -> bool: ...
+    def tree_names(self) -> list[bytes]: ...
+    def iter_tree_names(self) -> Iterator[bytes]: ...
+    def trees(self) -> list[SledTree]: ...
+    def was_recovered(self) -> bool: ...
+    def tree_stats(self) -> dict[bytes, int]: ...
+    def size_on_disk(self) -> int: ...
+    def export(self) -> list[tuple[bytes, bytes, SledIter]]: ...
+    def import_(self, collections: Iterable[tuple[_Bytes, _Bytes, Iterable[tuple[_Bytes, _Bytes]]]]) -> None: ...
+    def dump_to(self, path: _Path) -> None: ...
+    def load_from(self, path: _Path) -> None: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> Self: ...
+    def __exit__(self, exc_type: object, exc_value: object, traceback: object) -> bool: ...
+    def generate_id(self) -> int: ...
+    def generate_key(self, width: int = 8) -> bytes: ...
+
+class SledTypedTree(MutableMapping[Any, Any]):
+    def insert(self, key: Any, value: Any) -> Any | None: ...
+    def get(self, key: Any, default: Any = None) -> Any: ...
+    def remove(self, key: Any) -> Any | None: ...
+    def contains_key(self, key: Any) -> bool: ...
+    def clear(self) -> None: ...
+    def is_empty(self) -> bool: ...
+    def len(self) -> int: ...
+    def range(self, start: Any = None, end: Any = None, inclusive: bool = False) -> SledIter: ...
+    def keys(self) -> SledKeysView[Any]: ...
+    def values(self) -> SledValuesView[Any]: ...
+    def items(self) -> SledItemsView[Any, Any]: ...
+    def __len__(self) -> int: ...
+    def __contains__(self, key: object) -> bool: ...
+    def __getitem__(self, key: Any) -> Any: ...
+    def __setitem__(self, key: Any, value: Any) -> None: ...
+    def __delitem__(self, key: Any) -> None: ...
+    def __iter__(self) -> SledIter: ...
+    def pop(self, key: Any, *default: Any) -> Any: ...
+    def popitem(self) -> tuple[Any, Any]: ...
+    def setdefault(self, key: Any, default: Any) -> Any: ...
+    def update(self, other: Mapping[Any, Any] | Iterable[tuple[Any, Any]]) -> None: ...  # type: ignore[override]
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    @property
+    def name(self) -> bytes: ...
+    @property
+    def raw(self) -> SledTree: ...
+
+class SledConfig:
+    def __init__(self) -> None: ...
+    def path(self, path: _Path) -> Self: ...
+    def cache_capacity(self, to: int) -> Self: ...
+    def mode(self, mode: Literal["low_space", "high_throughput"]) -> Self: ...
+    def use_compression(self, to: bool) -> Self: ...
+    def compression_factor(self, to: int) -> Self: ...
+    def flush_every_ms(self, every_ms: int | None) -> Self: ...
+    def segment_size(self, segment_size: int) -> Self: ...
+    def temporary(self, to: bool) -> Self: ...
+    def create_new(self, to: bool) -> Self: ...
+    def open(self) -> SledDb: ...
+
+class SledIter(Iterator[Any]):
+    def __iter__(self) -> Self: ...
+    def __next__(self) -> Any: ...
+    def __reversed__(self) -> SledIter: ...
+
+class SledKeysView(KeysView[_K]):
+    def __len__(self) -> int: ...
+    def __iter__(self) -> SledIter: ...
+    def __reversed__(self) -> SledIter: ...
+    def __contains__(self, key: object) -> bool: ...
+    def __repr__(self) -> str: ...
+    def __and__(self, other: Iterable[Any]) -> set[_K]: ...
+    def __rand__(self, other: Iterable[_T]) -> set[_T]: ...
+    def __or__(self, other: Iterable[_T]) -> set[_K | _T]: ...
+    def __ror__(self, other: Iterable[_T]) -> set[_K | _T]: ...
+    def __sub__(self, other: Iterable[Any]) -> set[_K]: ...
+    def __rsub__(self, other: Iterable[_T]) -> set[_T]: ...
+    def __xor__(self, other: Iterable[_T]) -> set[_K | _T]: ...
+    def __rxor__(self, other: Iterable[_T]) -> set[_K | _T]: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def isdisjoint(self, other: Iterable[Any]) -> bool: ...
+
+class SledValuesView(ValuesView[_V]):
+    def __len__(self) -> int: ...
+    def __iter__(self) -> SledIter: ...
+    def __reversed__(self) -> SledIter: ...
+    def __contains__(self, value: object) -> bool: ...
+    def __repr__(self) -> str: ...
+
+class SledItemsView(ItemsView[_K, _V]):
+    def __len__(self) -> int: ...
+    def __iter__(self) -> SledIter: ...
+    def __reversed__(self) -> SledIter: ...
+    def __contains__(self, item: object) -> bool: ...
+    def __repr__(self) -> str: ...
+    def __and__(self, other: Iterable[Any]) -> set[tuple[_K, _V]]: ...
+    def __rand__(self, other: Iterable[_T]) -> set[_T]: ...
+    def __or__(self, other: Iterable[_T]) -> set[tuple[_K, _V] | _T]: ...
+    def __ror__(self, other: Iterable[_T]) -> set[tuple[_K, _V] | _T]: ...
+    def __sub__(self, other: Iterable[Any]) -> set[tuple[_K, _V]]: ...
+    def __rsub__(self, other: Iterable[_T]) -> set[_T]: ...
+    def __xor__(self, other: Iterable[_T]) -> set[tuple[_K, _V] | _T]: ...
+    def __rxor__(self, other: Iterable[_T]) -> set[tuple[_K, _V] | _T]: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+    def isdisjoint(self, other: Iterable[Any]) -> bool: ...
+
+class TransactionalTree:
+    def insert(self, key: _Bytes, value: _Bytes) -> list[int] | None: ...
+    def get(self, key: _Bytes) -> list[int] | None: ...
+    def remove(self, key: _Bytes) -> list[int] | None: ...
+
+class SledBatch:
+    def __init__(self) -> None: ...
+    def insert(self, key: _Bytes, value: _Bytes) -> None: ...
+    def remove(self, key: _Bytes) -> None: ...
+    def clear(self) -> None: ...
+    def is_empty(self) -> bool: ...
+    def len(self) -> int: ...
+    def __len__(self) -> int: ...
+
+class InsertEvent:
+    key: bytes
+    value: list[int]
+    def __repr__(self) -> str: ...
+
+class RemoveEvent:
+    key: bytes
+    def __repr__(self) -> str: ...
+
+class SledSubscriber:
+    def __iter__(self) -> Self: ...
+    def __next__(self) -> InsertEvent | RemoveEvent: ...
+    def next(self, timeout: float | None = None) -> InsertEvent | RemoveEvent | None: ...
+    def __aiter__(self) -> Self: ...
+    def __anext__(self) -> Awaitable[InsertEvent | RemoveEvent]: ...
+
+class SledValue:
+    def __bytes__(self) -> bytes: ...
+    def __len__(self) -> int: ...
+    def __repr__(self) -> str: ...
+
+class PickleCodec:
+    def __init__(self, protocol: int | None = None) -> None: ...
+    def encode(self, obj: Any) -> bytes: ...
+    def decode(self, data: bytes) -> Any: ...
+
+class SledError(Exception): ...
+
+class CollectionNotFound(SledError):
+    name: bytes
+
+class Unsupported(SledError): ...
+class ReportableBug(SledError): ...
+class IoError(SledError, OSError): ...
+
+class Corruption(SledError):
+    at: int | None
+    pointer: str | None
+
+class SledClosedError(SledError): ...
+class CodecError(SledError): ...
+
+class CompareAndSwapError(SledError):
+    current: list[int] | None
+    proposed: list[int] | None
+
+def transaction(trees: Sequence[SledTree], f: Callable[..., _T]) -> _T: ...
+# strict mode is process-wide, it also applies to databases opened by other libraries
+def set_strict_bytes(strict: bool) -> None: ...
+def strict_bytes() -> bool: ...
+def build_info() -> dict[str, Any]: ...
+
+__version__: str
+sled_version: str
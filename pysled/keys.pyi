@@ -0,0 +1,7 @@
+from typing import Any
+
+from . import _Bytes
+
+def pack(items: tuple[Any, ...]) -> bytes: ...
+def unpack(key: _Bytes) -> tuple[Any, ...]: ...
+def range_for(prefix: tuple[Any, ...]) -> tuple[bytes, bytes]: ...
@@ -0,0 +1,158 @@
+use std::{
+    future::Future,
+    sync::{Condvar, Mutex, MutexGuard, OnceLock},
+};
+
+use pyo3::prelude::*;
+use tokio::{
+    runtime::{Builder, Runtime},
+    sync::oneshot,
+};
+
+/// Tracks completions that are about to hand a result to the event loop, so interpreter shutdown
+/// can wait for them instead of tearing down Python while a runtime thread holds the GIL.
+struct Completions {
+    shutting_down: bool,
+    running: usize,
+}
+
+static COMPLETIONS: Mutex<Completions> = Mutex::new(Completions {
+    shutting_down: false,
+    running: 0,
+});
+static COMPLETIONS_DONE: Condvar = Condvar::new();
+
+fn completions() -> MutexGuard<'static, Completions> {
+    COMPLETIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    // sled's futures are driven by wakers from its own threads, so a single worker only has to
+    // shuffle finished results over to the event loops
+    RUNTIME.get_or_init(|| {
+        Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("pysled-async")
+            .build()
+            .expect("Failed to start the pysled async runtime")
+    })
+}
+
+/// Cancels the Rust side once the asyncio future is done, e.g. because its task was cancelled.
+#[pyclass]
+struct CancelOnDone {
+    tx: Option<oneshot::Sender<()>>,
+}
+
+#[pymethods]
+impl CancelOnDone {
+    fn __call__(&mut self, _fut: &PyAny) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(());
+        }
+    }
+}
+
+type Completion = Box<dyn FnOnce(Python, &PyAny) -> PyResult<()> + Send>;
+
+/// Runs on the event loop thread and resolves the asyncio future unless it was cancelled meanwhile.
+#[pyclass]
+struct Resolve {
+    fut: PyObject,
+    complete: Option<Completion>,
+}
+
+#[pymethods]
+impl Resolve {
+    fn __call__(&mut self, py: Python) -> PyResult<()> {
+        match self.complete.take() {
+            Some(complete) => complete(py, self.fut.as_ref(py)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Drives `fut` on the shared runtime and returns an asyncio future for the running event loop
+/// that resolves to `convert(output)`.
+///
+/// If the asyncio future gets cancelled after `fut` already finished, the output is handed to
+/// `restore` instead, so that e.g. a received event is not lost.
+pub(crate) fn future_into_py<'p, F, C, R>(
+    py: Python<'p>,
+    fut: F,
+    convert: C,
+    restore: R,
+) -> PyResult<&'p PyAny>
+where
+    F: Future + Send + 'static,
+    F::Output: Send,
+    C: FnOnce(Python, F::Output) -> PyResult<PyObject> + Send + 'static,
+    R: FnOnce(F::Output) + Send + 'static,
+{
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let py_fut = event_loop.call_method0("create_future")?;
+    let (cancel_tx, cancel_rx) = oneshot::channel();
+    py_fut.call_method1(
+        "add_done_callback",
+        (CancelOnDone {
+            tx: Some(cancel_tx),
+        },),
+    )?;
+
+    let event_loop: PyObject = event_loop.into();
+    let fut_ref: PyObject = py_fut.into();
+    runtime().spawn(async move {
+        let output = tokio::select! {
+            biased;
+            _ = cancel_rx => return,
+            output = fut => output,
+        };
+        let complete: Completion = Box::new(move |py, fut| {
+            if fut.call_method0("done")?.is_true()? {
+                restore(output);
+                return Ok(());
+            }
+            match convert(py, output) {
+                Ok(value) => fut.call_method1("set_result", (value,))?,
+                Err(e) => fut.call_method1("set_exception", (e,))?,
+            };
+            Ok(())
+        });
+        {
+            let mut completions = completions();
+            if completions.shutting_down {
+                return;
+            }
+            completions.running += 1;
+        }
+        Python::with_gil(|py| {
+            let resolve = Resolve {
+                fut: fut_ref,
+                complete: Some(complete),
+            };
+            // fails only if the loop was closed, in which case nobody is waiting for the result
+            let _ = event_loop.call_method1(py, "call_soon_threadsafe", (resolve,));
+        });
+        let mut completions = completions();
+        completions.running -= 1;
+        if completions.running == 0 {
+            COMPLETIONS_DONE.notify_all();
+        }
+    });
+    Ok(py_fut)
+}
+
+/// Registered with `atexit`: stops handing out results and waits for the ones in flight.
+#[pyfunction]
+pub(crate) fn shutdown(py: Python) {
+    py.allow_threads(|| {
+        let mut completions = completions();
+        completions.shutting_down = true;
+        while completions.running > 0 {
+            completions = COMPLETIONS_DONE
+                .wait(completions)
+                .unwrap_or_else(|e| e.into_inner());
+        }
+    })
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+use sled::{Batch, Tree};
+
+use crate::{bytes::Bytes, convert_to_pyresult};
+
+/// A set of inserts and removals that is applied atomically with `apply_batch`.
+///
+/// Later operations on the same key replace earlier ones, so `len` counts distinct keys.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct SledBatch {
+    writes: HashMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl SledBatch {
+    fn to_batch(&self) -> Batch {
+        let mut batch = Batch::default();
+        for (key, value) in &self.writes {
+            match value {
+                Some(value) => batch.insert(key.as_slice(), value.as_slice()),
+                None => batch.remove(key.as_slice()),
+            }
+        }
+        batch
+    }
+}
+
+#[pymethods]
+impl SledBatch {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: Bytes, value: Bytes) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    pub fn remove(&mut self, key: Bytes) {
+        self.writes.insert(key.to_vec(), None);
+    }
+
+    pub fn clear(&mut self) {
+        self.writes.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.writes.len()
+    }
+}
+
+pub(crate) fn apply_batch(py: Python, tree: &Tree, batch: &SledBatch) -> PyResult<()> {
+    let batch = batch.to_batch();
+    convert_to_pyresult(py.allow_threads(|| tree.apply_batch(batch)))
+}
+
+/// Inserts every `(key, value)` pair of `pairs` as one atomic batch.
+pub(crate) fn insert_many(py: Python, tree: &Tree, pairs: &PyAny) -> PyResult<()> {
+    let mut batch = Batch::default();
+    for pair in pairs.iter()? {
+        let (key, value): (Bytes, Bytes) = pair?.extract()?;
+        batch.insert(key, value);
+    }
+    convert_to_pyresult(py.allow_threads(|| tree.apply_batch(batch)))
+}
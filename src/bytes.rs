@@ -0,0 +1,93 @@
+use std::{
+    ops::Deref,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use pyo3::{
+    buffer::PyBuffer,
+    exceptions::PyTypeError,
+    prelude::*,
+    types::{PyBytes, PyString},
+};
+use sled::IVec;
+
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// A key or value passed in from Python.
+///
+/// Accepts `bytes` without copying, any object supporting the buffer protocol such as
+/// `bytearray`, `memoryview` or `SledValue`, `str`, which is encoded as UTF-8 unless strict mode
+/// is on, and sequences of ints, the `list[int]` values are read back as.
+pub enum Bytes<'a> {
+    Borrowed(&'a [u8]),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Bytes<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Bytes::Borrowed(b) => b,
+            Bytes::Owned(b) => b,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Bytes<'_> {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Bytes<'_>> for IVec {
+    fn from(bytes: Bytes<'_>) -> Self {
+        IVec::from(&*bytes)
+    }
+}
+
+impl<'a> FromPyObject<'a> for Bytes<'a> {
+    fn extract(obj: &'a PyAny) -> PyResult<Self> {
+        if let Ok(b) = obj.downcast::<PyBytes>() {
+            return Ok(Bytes::Borrowed(b.as_bytes()));
+        }
+        if let Ok(s) = obj.downcast::<PyString>() {
+            if STRICT.load(Ordering::Relaxed) {
+                return Err(PyTypeError::new_err(
+                    "str keys and values are rejected in strict mode, encode them to bytes first",
+                ));
+            }
+            return Ok(Bytes::Borrowed(s.to_str()?.as_bytes()));
+        }
+        // copied, since the buffer may change once the GIL is released
+        if let Ok(buffer) = PyBuffer::<u8>::get(obj) {
+            return Ok(Bytes::Owned(buffer.to_vec(obj.py())?));
+        }
+        match obj.extract::<Vec<u8>>() {
+            Ok(ints) => Ok(Bytes::Owned(ints)),
+            Err(_) => {
+                let name = obj.get_type().name().unwrap_or("object");
+                Err(PyTypeError::new_err(format!(
+                    "expected a bytes-like object, str or sequence of ints, not {}",
+                    name
+                )))
+            }
+        }
+    }
+}
+
+/// With `strict=True`, keys and values given as `str` raise `TypeError` instead of being encoded
+/// as UTF-8.
+///
+/// The mode is global: it applies to every database in the process, including ones opened by
+/// other libraries, so it is meant to be set once by the application rather than by a library.
+#[pyfunction]
+pub fn set_strict_bytes(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+/// Whether `str` keys and values are rejected, see `set_strict_bytes`.
+#[pyfunction]
+pub fn strict_bytes() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
@@ -0,0 +1,88 @@
+use pyo3::{prelude::*, types::PyBytes};
+use sled::Tree;
+
+use crate::{bytes::Bytes, convert_to_pyresult, error::CompareAndSwapError};
+
+/// Sets `key` to `new` if its value is `old`, with `None` meaning absent. Raises
+/// `CompareAndSwapError` otherwise.
+pub(crate) fn compare_and_swap(
+    py: Python,
+    tree: &Tree,
+    key: &[u8],
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+) -> PyResult<()> {
+    let result = convert_to_pyresult(py.allow_threads(|| tree.compare_and_swap(key, old, new)))?;
+    result.map_err(|e| {
+        let err = CompareAndSwapError::new_err("Compare and swap conflict");
+        let value = err.value(py);
+        let _ = value.setattr("current", e.current.map(|v| v.to_vec()));
+        let _ = value.setattr("proposed", e.proposed.map(|v| v.to_vec()));
+        err
+    })
+}
+
+/// The old misspelled variant, which returns the `CompareAndSwapError` instead of raising it.
+pub(crate) fn compare_and_swamp(
+    py: Python,
+    tree: &Tree,
+    key: &[u8],
+    old: Option<&[u8]>,
+    new: Option<&[u8]>,
+) -> PyResult<Option<PyObject>> {
+    PyErr::warn(
+        py,
+        py.import("builtins")?.getattr("DeprecationWarning")?,
+        "compare_and_swamp is deprecated, use compare_and_swap instead",
+        1,
+    )?;
+    match compare_and_swap(py, tree, key, old, new) {
+        Ok(()) => Ok(None),
+        Err(e) if e.is_instance_of::<CompareAndSwapError>(py) => Ok(Some(e.into_value(py).into())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Runs `f` on the current value of `key` and stores its result, retrying with the newer value
+/// whenever another writer got in between. Returns the new value if `return_new` is set and the
+/// replaced one otherwise.
+fn update(
+    py: Python,
+    tree: &Tree,
+    key: &[u8],
+    f: &PyAny,
+    return_new: bool,
+) -> PyResult<Option<Vec<u8>>> {
+    // a loop of our own instead of sled's, so exceptions raised by `f` stop it
+    let mut current = convert_to_pyresult(py.allow_threads(|| tree.get(key)))?;
+    loop {
+        let old = current.as_deref().map(|v| PyBytes::new(py, v));
+        let new: Option<Bytes> = f.call1((old,))?.extract()?;
+        let swapped = convert_to_pyresult(
+            py.allow_threads(|| tree.compare_and_swap(key, current.as_ref(), new.as_deref())),
+        )?;
+        match swapped {
+            Ok(()) if return_new => return Ok(new.map(|v| v.to_vec())),
+            Ok(()) => return Ok(current.map(|v| v.to_vec())),
+            Err(e) => current = e.current,
+        }
+    }
+}
+
+pub(crate) fn update_and_fetch(
+    py: Python,
+    tree: &Tree,
+    key: &[u8],
+    f: &PyAny,
+) -> PyResult<Option<Vec<u8>>> {
+    update(py, tree, key, f, true)
+}
+
+pub(crate) fn fetch_and_update(
+    py: Python,
+    tree: &Tree,
+    key: &[u8],
+    f: &PyAny,
+) -> PyResult<Option<Vec<u8>>> {
+    update(py, tree, key, f, false)
+}
@@ -0,0 +1,328 @@
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple},
+};
+use rmpv::Value as MsgpackValue;
+use serde_json::{Map, Number, Value as JsonValue};
+
+use crate::{bytes::Bytes, error::CodecError};
+
+// deeper structures are rejected instead of risking a stack overflow while converting them
+const MAX_DEPTH: usize = 256;
+
+/// How a typed view turns Python objects into the bytes stored in sled and back.
+pub(crate) enum Codec {
+    Bytes,
+    Str,
+    /// Signed 64 bit integers, big-endian with the sign bit flipped so they sort numerically.
+    Int,
+    Json,
+    Msgpack,
+    /// Any object with `encode(obj) -> bytes` and `decode(data) -> obj` methods, like `PickleCodec`.
+    Custom(PyObject),
+}
+
+/// The codecs of a typed view, shared with the iterators it hands out.
+pub(crate) struct Codecs {
+    pub(crate) key: Codec,
+    pub(crate) value: Codec,
+}
+
+impl Codecs {
+    pub(crate) fn encode_key(&self, key: &PyAny) -> PyResult<Vec<u8>> {
+        self.key.encode(key, "key")
+    }
+
+    pub(crate) fn encode_value(&self, value: &PyAny) -> PyResult<Vec<u8>> {
+        self.value.encode(value, "value")
+    }
+
+    pub(crate) fn decode_key(&self, py: Python, key: &[u8]) -> PyResult<PyObject> {
+        self.key.decode(py, key, "key")
+    }
+
+    pub(crate) fn decode_value(&self, py: Python, value: &[u8]) -> PyResult<PyObject> {
+        self.value.decode(py, value, "value")
+    }
+}
+
+fn codec_error(py: Python, action: &str, cause: PyErr) -> PyErr {
+    let err = CodecError::new_err(format!("Failed to {}: {}", action, cause.value(py)));
+    err.set_cause(py, Some(cause));
+    err
+}
+
+fn too_deep() -> PyErr {
+    PyValueError::new_err("the value is nested too deeply")
+}
+
+impl Codec {
+    /// Accepts `None` for raw bytes, the name of a builtin codec, or a custom codec object.
+    pub(crate) fn from_py(py: Python, codec: Option<&PyAny>) -> PyResult<Self> {
+        let codec = match codec {
+            Some(codec) => codec,
+            None => return Ok(Codec::Bytes),
+        };
+        if let Ok(name) = codec.extract::<&str>() {
+            return match name {
+                "bytes" => Ok(Codec::Bytes),
+                "str" => Ok(Codec::Str),
+                "int" => Ok(Codec::Int),
+                "json" => Ok(Codec::Json),
+                "msgpack" => Ok(Codec::Msgpack),
+                "pickle" => Ok(Codec::Custom(
+                    Py::new(py, PickleCodec::new(None))?.into_py(py),
+                )),
+                other => Err(PyValueError::new_err(format!(
+                    "Unknown codec {:?}, expected \"bytes\", \"str\", \"int\", \"json\", \
+                     \"msgpack\" or \"pickle\"",
+                    other
+                ))),
+            };
+        }
+        if codec.hasattr("encode")? && codec.hasattr("decode")? {
+            return Ok(Codec::Custom(codec.into()));
+        }
+        Err(PyTypeError::new_err(
+            "codec must be the name of a builtin codec or an object with encode and decode methods",
+        ))
+    }
+
+    /// Encodes `obj`, the `what` ("key" or "value") of an entry, raising `CodecError` if this
+    /// codec cannot represent it.
+    fn encode(&self, obj: &PyAny, what: &str) -> PyResult<Vec<u8>> {
+        self.try_encode(obj)
+            .map_err(|e| codec_error(obj.py(), &format!("encode {}", what), e))
+    }
+
+    /// Decodes the stored `what` ("key" or "value") of an entry, raising `CodecError` if it was
+    /// not written by this codec.
+    fn decode(&self, py: Python, data: &[u8], what: &str) -> PyResult<PyObject> {
+        self.try_decode(py, data)
+            .map_err(|e| codec_error(py, &format!("decode {}", what), e))
+    }
+
+    fn try_encode(&self, obj: &PyAny) -> PyResult<Vec<u8>> {
+        match self {
+            Codec::Bytes => Ok(obj.extract::<Bytes>()?.to_vec()),
+            Codec::Str => Ok(obj.extract::<&str>()?.as_bytes().to_vec()),
+            Codec::Int => {
+                if obj.is_instance_of::<PyBool>()? {
+                    return Err(PyTypeError::new_err("expected an int, not a bool"));
+                }
+                let n: i64 = obj.extract()?;
+                Ok(((n as u64) ^ (1 << 63)).to_be_bytes().to_vec())
+            }
+            Codec::Json => serde_json::to_vec(&to_json(obj, 0)?)
+                .map_err(|e| PyValueError::new_err(e.to_string())),
+            Codec::Msgpack => {
+                let mut out = vec![];
+                rmpv::encode::write_value(&mut out, &to_msgpack(obj, 0)?)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                Ok(out)
+            }
+            Codec::Custom(codec) => {
+                let encoded = codec.as_ref(obj.py()).call_method1("encode", (obj,))?;
+                Ok(encoded.downcast::<PyBytes>()?.as_bytes().to_vec())
+            }
+        }
+    }
+
+    fn try_decode(&self, py: Python, data: &[u8]) -> PyResult<PyObject> {
+        match self {
+            Codec::Bytes => Ok(PyBytes::new(py, data).into()),
+            Codec::Str => std::str::from_utf8(data)
+                .map(|s| s.into_py(py))
+                .map_err(|e| PyValueError::new_err(e.to_string())),
+            Codec::Int => {
+                let bytes: [u8; 8] = data.try_into().map_err(|_| {
+                    PyValueError::new_err(format!("expected 8 bytes, got {}", data.len()))
+                })?;
+                Ok(((u64::from_be_bytes(bytes) ^ (1 << 63)) as i64).into_py(py))
+            }
+            Codec::Json => {
+                let value: JsonValue = serde_json::from_slice(data)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                from_json(py, &value)
+            }
+            Codec::Msgpack => {
+                let value = rmpv::decode::read_value(&mut &*data)
+                    .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                from_msgpack(py, &value)
+            }
+            Codec::Custom(codec) => {
+                Ok(codec.call_method1(py, "decode", (PyBytes::new(py, data),))?)
+            }
+        }
+    }
+}
+
+fn to_json(obj: &PyAny, depth: usize) -> PyResult<JsonValue> {
+    if depth > MAX_DEPTH {
+        return Err(too_deep());
+    }
+    if obj.is_none() {
+        Ok(JsonValue::Null)
+    } else if let Ok(b) = obj.downcast::<PyBool>() {
+        Ok(JsonValue::Bool(b.is_true()))
+    } else if obj.is_instance_of::<PyLong>()? {
+        match obj.extract::<i64>() {
+            Ok(n) => Ok(n.into()),
+            Err(_) => Ok(obj.extract::<u64>()?.into()),
+        }
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        Number::from_f64(f.value())
+            .map(JsonValue::Number)
+            .ok_or_else(|| PyValueError::new_err("JSON cannot represent NaN or infinity"))
+    } else if let Ok(s) = obj.downcast::<PyString>() {
+        Ok(JsonValue::String(s.to_str()?.to_owned()))
+    } else if obj.is_instance_of::<PyList>()? || obj.is_instance_of::<PyTuple>()? {
+        obj.iter()?
+            .map(|item| to_json(item?, depth + 1))
+            .collect::<PyResult<_>>()
+            .map(JsonValue::Array)
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, value) in dict {
+            let key = key
+                .downcast::<PyString>()
+                .map_err(|_| PyTypeError::new_err("JSON object keys must be strings"))?;
+            map.insert(key.to_str()?.to_owned(), to_json(value, depth + 1)?);
+        }
+        Ok(JsonValue::Object(map))
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "{} is not JSON serializable",
+            obj.get_type().name()?
+        )))
+    }
+}
+
+fn from_json(py: Python, value: &JsonValue) -> PyResult<PyObject> {
+    Ok(match value {
+        JsonValue::Null => py.None(),
+        JsonValue::Bool(b) => b.into_py(py),
+        JsonValue::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(n), _, _) => n.into_py(py),
+            (None, Some(n), _) => n.into_py(py),
+            (None, None, n) => n.unwrap_or(f64::NAN).into_py(py),
+        },
+        JsonValue::String(s) => s.into_py(py),
+        JsonValue::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(from_json(py, item)?)?;
+            }
+            list.into()
+        }
+        JsonValue::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, from_json(py, value)?)?;
+            }
+            dict.into()
+        }
+    })
+}
+
+fn to_msgpack(obj: &PyAny, depth: usize) -> PyResult<MsgpackValue> {
+    if depth > MAX_DEPTH {
+        return Err(too_deep());
+    }
+    if obj.is_none() {
+        Ok(MsgpackValue::Nil)
+    } else if let Ok(b) = obj.downcast::<PyBool>() {
+        Ok(MsgpackValue::Boolean(b.is_true()))
+    } else if obj.is_instance_of::<PyLong>()? {
+        match obj.extract::<i64>() {
+            Ok(n) => Ok(n.into()),
+            Err(_) => Ok(obj.extract::<u64>()?.into()),
+        }
+    } else if let Ok(f) = obj.downcast::<PyFloat>() {
+        Ok(MsgpackValue::F64(f.value()))
+    } else if let Ok(s) = obj.downcast::<PyString>() {
+        Ok(s.to_str()?.into())
+    } else if let Ok(b) = obj.downcast::<PyBytes>() {
+        Ok(MsgpackValue::Binary(b.as_bytes().to_vec()))
+    } else if obj.is_instance_of::<PyList>()? || obj.is_instance_of::<PyTuple>()? {
+        obj.iter()?
+            .map(|item| to_msgpack(item?, depth + 1))
+            .collect::<PyResult<_>>()
+            .map(MsgpackValue::Array)
+    } else if let Ok(dict) = obj.downcast::<PyDict>() {
+        dict.iter()
+            .map(|(key, value)| Ok((to_msgpack(key, depth + 1)?, to_msgpack(value, depth + 1)?)))
+            .collect::<PyResult<_>>()
+            .map(MsgpackValue::Map)
+    } else {
+        Err(PyTypeError::new_err(format!(
+            "{} is not msgpack serializable",
+            obj.get_type().name()?
+        )))
+    }
+}
+
+fn from_msgpack(py: Python, value: &MsgpackValue) -> PyResult<PyObject> {
+    Ok(match value {
+        MsgpackValue::Nil => py.None(),
+        MsgpackValue::Boolean(b) => b.into_py(py),
+        MsgpackValue::Integer(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => n.into_py(py),
+            (None, Some(n)) => n.into_py(py),
+            (None, None) => unreachable!("msgpack integers fit into i64 or u64"),
+        },
+        MsgpackValue::F32(f) => f.into_py(py),
+        MsgpackValue::F64(f) => f.into_py(py),
+        MsgpackValue::String(s) => match s.as_str() {
+            Some(s) => s.into_py(py),
+            None => return Err(PyValueError::new_err("msgpack string is not valid UTF-8")),
+        },
+        MsgpackValue::Binary(b) => PyBytes::new(py, b).into(),
+        MsgpackValue::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(from_msgpack(py, item)?)?;
+            }
+            list.into()
+        }
+        MsgpackValue::Map(entries) => {
+            let dict = PyDict::new(py);
+            for (key, value) in entries {
+                dict.set_item(from_msgpack(py, key)?, from_msgpack(py, value)?)?;
+            }
+            dict.into()
+        }
+        MsgpackValue::Ext(..) => {
+            return Err(PyValueError::new_err(
+                "msgpack extension types are not supported",
+            ))
+        }
+    })
+}
+
+/// Stores arbitrary Python objects with `pickle`. Only decode data from sources you trust.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct PickleCodec {
+    protocol: Option<i32>,
+}
+
+#[pymethods]
+impl PickleCodec {
+    /// `protocol` defaults to `pickle.DEFAULT_PROTOCOL`.
+    #[new]
+    #[args(protocol = "None")]
+    pub fn new(protocol: Option<i32>) -> Self {
+        Self { protocol }
+    }
+
+    pub fn encode<'p>(&self, py: Python<'p>, obj: &PyAny) -> PyResult<&'p PyAny> {
+        py.import("pickle")?
+            .call_method1("dumps", (obj, self.protocol))
+    }
+
+    pub fn decode<'p>(&self, py: Python<'p>, data: &PyAny) -> PyResult<&'p PyAny> {
+        py.import("pickle")?.call_method1("loads", (data,))
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::PathBuf;
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use sled::{Config, Mode};
+
+use crate::{convert_to_pyresult, SledDb};
+
+pub(crate) fn parse_mode(mode: &str) -> PyResult<Mode> {
+    match mode {
+        "low_space" => Ok(Mode::LowSpace),
+        "high_throughput" => Ok(Mode::HighThroughput),
+        other => Err(PyValueError::new_err(format!(
+            "Unknown mode {:?}, expected \"low_space\" or \"high_throughput\"",
+            other
+        ))),
+    }
+}
+
+pub(crate) fn open_config(py: Python, config: &Config) -> PyResult<PyClassInitializer<SledDb>> {
+    let inner = convert_to_pyresult(py.allow_threads(|| config.open()))?;
+    Ok(SledDb::init(inner))
+}
+
+/// Builder for opening a `SledDb` with non-default settings, mirroring `sled::Config`.
+///
+/// The options are collected here and only turned into a `sled::Config` on `open`, so the
+/// same builder can be reused to open several databases.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct SledConfig {
+    path: Option<PathBuf>,
+    cache_capacity: Option<u64>,
+    mode: Option<Mode>,
+    use_compression: Option<bool>,
+    compression_factor: Option<i32>,
+    flush_every_ms: Option<Option<u64>>,
+    segment_size: Option<usize>,
+    temporary: Option<bool>,
+    create_new: Option<bool>,
+}
+
+impl SledConfig {
+    pub(crate) fn build(&self) -> Config {
+        let mut config = Config::new();
+        if let Some(path) = &self.path {
+            config = config.path(path);
+        }
+        if let Some(cache_capacity) = self.cache_capacity {
+            config = config.cache_capacity(cache_capacity);
+        }
+        if let Some(mode) = self.mode {
+            config = config.mode(mode);
+        }
+        if let Some(use_compression) = self.use_compression {
+            config = config.use_compression(use_compression);
+        }
+        if let Some(compression_factor) = self.compression_factor {
+            config = config.compression_factor(compression_factor);
+        }
+        if let Some(flush_every_ms) = self.flush_every_ms {
+            config = config.flush_every_ms(flush_every_ms);
+        }
+        if let Some(segment_size) = self.segment_size {
+            config = config.segment_size(segment_size);
+        }
+        if let Some(temporary) = self.temporary {
+            config = config.temporary(temporary);
+        }
+        if let Some(create_new) = self.create_new {
+            config = config.create_new(create_new);
+        }
+        config
+    }
+}
+
+#[pymethods]
+impl SledConfig {
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn path(mut slf: PyRefMut<Self>, path: PathBuf) -> PyRefMut<Self> {
+        slf.path = Some(path);
+        slf
+    }
+
+    pub fn cache_capacity(mut slf: PyRefMut<Self>, to: u64) -> PyRefMut<Self> {
+        slf.cache_capacity = Some(to);
+        slf
+    }
+
+    /// Either "low_space" or "high_throughput".
+    pub fn mode<'a>(mut slf: PyRefMut<'a, Self>, mode: &str) -> PyResult<PyRefMut<'a, Self>> {
+        slf.mode = Some(parse_mode(mode)?);
+        Ok(slf)
+    }
+
+    pub fn use_compression(mut slf: PyRefMut<Self>, to: bool) -> PyRefMut<Self> {
+        slf.use_compression = Some(to);
+        slf
+    }
+
+    pub fn compression_factor(mut slf: PyRefMut<Self>, to: i32) -> PyRefMut<Self> {
+        slf.compression_factor = Some(to);
+        slf
+    }
+
+    /// Passing `None` disables the background flusher.
+    pub fn flush_every_ms(mut slf: PyRefMut<Self>, every_ms: Option<u64>) -> PyRefMut<Self> {
+        slf.flush_every_ms = Some(every_ms);
+        slf
+    }
+
+    pub fn segment_size(mut slf: PyRefMut<Self>, segment_size: usize) -> PyRefMut<Self> {
+        slf.segment_size = Some(segment_size);
+        slf
+    }
+
+    pub fn temporary(mut slf: PyRefMut<Self>, to: bool) -> PyRefMut<Self> {
+        slf.temporary = Some(to);
+        slf
+    }
+
+    pub fn create_new(mut slf: PyRefMut<Self>, to: bool) -> PyRefMut<Self> {
+        slf.create_new = Some(to);
+        slf
+    }
+
+    pub fn open(&self, py: Python) -> PyResult<Py<SledDb>> {
+        Py::new(py, open_config(py, &self.build())?)
+    }
+}
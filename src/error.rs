@@ -0,0 +1,111 @@
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyOSError},
+    once_cell::GILOnceCell,
+    prelude::*,
+    types::{PyBytes, PyDict, PyType},
+};
+
+create_exception!(
+    pysled,
+    SledError,
+    PyException,
+    "Base class of all errors raised by sled."
+);
+create_exception!(
+    pysled,
+    CollectionNotFound,
+    SledError,
+    "The tree no longer exists. Its name is available as `name`."
+);
+create_exception!(
+    pysled,
+    Unsupported,
+    SledError,
+    "sled has been used in an unsupported way."
+);
+create_exception!(
+    pysled,
+    ReportableBug,
+    SledError,
+    "sled hit an unexpected bug, which should be reported upstream."
+);
+create_exception!(
+    pysled,
+    Corruption,
+    SledError,
+    "Corruption has been detected in the storage file. The log offset it was found at is \
+     available as `at` and the raw location as `pointer`, both `None` if unknown."
+);
+create_exception!(
+    pysled,
+    SledClosedError,
+    SledError,
+    "The database, or the database a tree or iterator belongs to, has been closed."
+);
+create_exception!(
+    pysled,
+    CodecError,
+    SledError,
+    "A typed tree could not encode a value, or found stored bytes it could not decode. The \
+     original error is chained as `__cause__`."
+);
+create_exception!(
+    pysled,
+    CompareAndSwapError,
+    SledError,
+    "The value did not match the expected one. The value found is available as `current` and \
+     the value that could not be stored as `proposed`."
+);
+
+/// `IoError` subclasses both `SledError` and `OSError`, which `create_exception!` cannot express,
+/// so the class is created through `type()` the first time it is needed.
+pub(crate) fn io_error(py: Python<'_>) -> &PyType {
+    static IO_ERROR: GILOnceCell<Py<PyType>> = GILOnceCell::new();
+    IO_ERROR
+        .get_or_init(py, || {
+            let bases = (py.get_type::<SledError>(), py.get_type::<PyOSError>());
+            let dict = PyDict::new(py);
+            let init = || -> PyResult<Py<PyType>> {
+                dict.set_item("__module__", "pysled")?;
+                dict.set_item(
+                    "__doc__",
+                    "A read or write error while interacting with the file system. Carries \
+                     `errno` like any other `OSError`.",
+                )?;
+                Ok(py
+                    .get_type::<PyType>()
+                    .call1(("IoError", bases, dict))?
+                    .downcast::<PyType>()?
+                    .into())
+            };
+            init().expect("Failed to create pysled.IoError")
+        })
+        .as_ref(py)
+}
+
+pub(crate) fn to_pyerr(py: Python, err: sled::Error) -> PyErr {
+    let message = err.to_string();
+    match err {
+        sled::Error::CollectionNotFound(name) => {
+            let err = CollectionNotFound::new_err(message);
+            let _ = err
+                .value(py)
+                .setattr("name", PyBytes::new(py, &name).to_object(py));
+            err
+        }
+        sled::Error::Unsupported(_) => Unsupported::new_err(message),
+        sled::Error::ReportableBug(_) => ReportableBug::new_err(message),
+        sled::Error::Io(e) => match e.raw_os_error() {
+            Some(errno) => PyErr::from_type(io_error(py), (errno, e.to_string())),
+            None => PyErr::from_type(io_error(py), (message,)),
+        },
+        sled::Error::Corruption { at, .. } => {
+            let err = Corruption::new_err(message);
+            let value = err.value(py);
+            let _ = value.setattr("at", at.map(|at| at.lid()));
+            let _ = value.setattr("pointer", at.map(|at| format!("{:?}", at)));
+            err
+        }
+    }
+}
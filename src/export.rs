@@ -0,0 +1,261 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use sled::{Batch, Db, Tree};
+
+use crate::{
+    bytes::Bytes,
+    convert_to_pyresult,
+    handle::Registry,
+    iter::{IterKind, SledIter},
+};
+
+const COLLECTION_TREE: &[u8] = b"tree";
+
+// imported entries are applied in batches of this size rather than one at a time
+const IMPORT_BATCH_SIZE: usize = 1024;
+
+// dump file layout, all integers big-endian:
+//   magic, u32 format version
+//   per collection: TAG_COLLECTION, u32 + type, u32 + name
+//   per entry: TAG_ENTRY, u32 + key, u32 + value
+//   TAG_END
+const MAGIC: &[u8; 8] = b"PYSLEDDB";
+const FORMAT_VERSION: u32 = 1;
+const TAG_END: u8 = 0;
+const TAG_COLLECTION: u8 = 1;
+const TAG_ENTRY: u8 = 2;
+
+/// Lists every collection of `db` as `(collection_type, name, items)`, where `items` lazily
+/// yields the `(key, value)` pairs as `bytes`.
+pub(crate) fn export(
+    py: Python,
+    registry: &Registry,
+    db: &Db,
+) -> PyResult<Vec<(PyObject, PyObject, SledIter)>> {
+    let mut collections = vec![];
+    for name in db.tree_names() {
+        let tree = convert_to_pyresult(py.allow_threads(|| db.open_tree(&name)))?;
+        collections.push((
+            PyBytes::new(py, COLLECTION_TREE).into(),
+            PyBytes::new(py, &name).into(),
+            SledIter::new(registry, tree.iter(), IterKind::RawItems),
+        ));
+    }
+    Ok(collections)
+}
+
+/// The trees an import has opened, so a failed import can leave the database as it found it.
+#[derive(Default)]
+struct Imported {
+    // the name of each tree and whether it existed before the import
+    trees: Vec<(Vec<u8>, bool)>,
+}
+
+impl Imported {
+    /// Opens the tree a collection is imported into, refusing to mix it with existing data.
+    fn target(
+        &mut self,
+        db: &Db,
+        collection_type: &[u8],
+        name: &[u8],
+    ) -> sled::Result<Result<Tree, String>> {
+        if collection_type != COLLECTION_TREE {
+            return Ok(Err(format!(
+                "Unknown collection type {:?}",
+                String::from_utf8_lossy(collection_type)
+            )));
+        }
+        let existed = db.tree_names().iter().any(|n| n == name);
+        let tree = db.open_tree(name)?;
+        if !tree.is_empty() {
+            return Ok(Err(format!(
+                "Cannot import into the non-empty tree {:?}",
+                String::from_utf8_lossy(name)
+            )));
+        }
+        self.trees.push((name.to_vec(), existed));
+        Ok(Ok(tree))
+    }
+
+    /// Empties the trees opened so far again, and drops the ones the import created.
+    fn undo(&self, db: &Db) -> sled::Result<()> {
+        // newest first, so a tree created earlier in the import is cleared before it is dropped
+        for (name, existed) in self.trees.iter().rev() {
+            if *existed {
+                db.open_tree(name)?.clear()?;
+            } else {
+                db.drop_tree(name)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn checked<T>(result: sled::Result<Result<T, String>>) -> PyResult<T> {
+    convert_to_pyresult(result)?.map_err(PyValueError::new_err)
+}
+
+/// Imports collections in the shape produced by `export`. Every target tree must be empty. If
+/// the import fails, the trees it wrote to are emptied again.
+pub(crate) fn import(py: Python, db: &Db, collections: &PyAny) -> PyResult<()> {
+    let mut imported = Imported::default();
+    let result = import_into(py, db, collections, &mut imported);
+    if result.is_err() {
+        convert_to_pyresult(py.allow_threads(|| imported.undo(db)))?;
+    }
+    result
+}
+
+fn import_into(py: Python, db: &Db, collections: &PyAny, imported: &mut Imported) -> PyResult<()> {
+    for collection in collections.iter()? {
+        let (collection_type, name, items): (Bytes, Bytes, &PyAny) = collection?.extract()?;
+        let tree = checked(py.allow_threads(|| imported.target(db, &collection_type, &name)))?;
+        let mut batch = Batch::default();
+        let mut batched = 0;
+        for item in items.iter()? {
+            let (key, value): (Bytes, Bytes) = item?.extract()?;
+            batch.insert(key, value);
+            batched += 1;
+            if batched == IMPORT_BATCH_SIZE {
+                let full = std::mem::take(&mut batch);
+                convert_to_pyresult(py.allow_threads(|| tree.apply_batch(full)))?;
+                batched = 0;
+            }
+        }
+        convert_to_pyresult(py.allow_threads(|| tree.apply_batch(batch)))?;
+    }
+    Ok(())
+}
+
+fn write_bytes(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(bytes.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "entry too large to dump"))?;
+    out.write_all(&len.to_be_bytes())?;
+    out.write_all(bytes)
+}
+
+fn dump(db: &Db, path: &Path) -> sled::Result<()> {
+    let file = OpenOptions::new().write(true).create_new(true).open(path)?;
+    let written = write_dump(db, file);
+    if written.is_err() {
+        // a partial dump must not be left behind to be mistaken for a complete one
+        let _ = fs::remove_file(path);
+    }
+    written
+}
+
+fn write_dump(db: &Db, file: File) -> sled::Result<()> {
+    let mut out = BufWriter::new(file);
+    out.write_all(MAGIC)?;
+    out.write_all(&FORMAT_VERSION.to_be_bytes())?;
+    for name in db.tree_names() {
+        out.write_all(&[TAG_COLLECTION])?;
+        write_bytes(&mut out, COLLECTION_TREE)?;
+        write_bytes(&mut out, &name)?;
+        for entry in db.open_tree(&name)?.iter() {
+            let (key, value) = entry?;
+            out.write_all(&[TAG_ENTRY])?;
+            write_bytes(&mut out, &key)?;
+            write_bytes(&mut out, &value)?;
+        }
+    }
+    out.write_all(&[TAG_END])?;
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(())
+}
+
+/// Writes all collections of `db` to a new file at `path` in pysled's portable dump format.
+/// Fails if `path` already exists, and removes the file again if writing fails.
+pub(crate) fn dump_to(py: Python, db: &Db, path: &Path) -> PyResult<()> {
+    convert_to_pyresult(py.allow_threads(|| dump(db, path)))
+}
+
+fn read_u8(input: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    input.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_bytes(input: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_u32(input)? as usize;
+    let mut buf = vec![];
+    input.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() != len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn load(db: &Db, path: &Path) -> sled::Result<Result<(), String>> {
+    let mut imported = Imported::default();
+    let result = match read_dump(db, path, &mut imported) {
+        Err(sled::Error::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            Ok(Err("The dump file is truncated".to_owned()))
+        }
+        result => result,
+    };
+    if !matches!(result, Ok(Ok(()))) {
+        imported.undo(db)?;
+    }
+    result
+}
+
+fn read_dump(db: &Db, path: &Path, imported: &mut Imported) -> sled::Result<Result<(), String>> {
+    let mut input = BufReader::new(File::open(path)?);
+    let mut magic = [0; MAGIC.len()];
+    if input.read_exact(&mut magic).is_err() || &magic != MAGIC {
+        return Ok(Err("Not a pysled dump file".to_owned()));
+    }
+    let version = read_u32(&mut input)?;
+    if version != FORMAT_VERSION {
+        return Ok(Err(format!(
+            "Unsupported dump format version {}, expected {}",
+            version, FORMAT_VERSION
+        )));
+    }
+    let mut tree: Option<Tree> = None;
+    let mut batch = Batch::default();
+    let mut batched = 0;
+    loop {
+        let tag = read_u8(&mut input)?;
+        if tag != TAG_ENTRY || batched == IMPORT_BATCH_SIZE {
+            if let Some(tree) = &tree {
+                tree.apply_batch(std::mem::take(&mut batch))?;
+            }
+            batched = 0;
+        }
+        match tag {
+            TAG_END => return Ok(Ok(())),
+            TAG_COLLECTION => {
+                let collection_type = read_bytes(&mut input)?;
+                let name = read_bytes(&mut input)?;
+                match imported.target(db, &collection_type, &name)? {
+                    Ok(target) => tree = Some(target),
+                    Err(e) => return Ok(Err(e)),
+                }
+            }
+            TAG_ENTRY if tree.is_some() => {
+                batch.insert(read_bytes(&mut input)?, read_bytes(&mut input)?);
+                batched += 1;
+            }
+            other => return Ok(Err(format!("Unexpected record tag {} in dump file", other))),
+        }
+    }
+}
+
+/// Imports a file written by `dump_to`. Every target tree must be empty. If the file turns out
+/// to be truncated or corrupt, the trees written to are emptied again.
+pub(crate) fn load_from(py: Python, db: &Db, path: &Path) -> PyResult<()> {
+    checked(py.allow_threads(|| load(db, path)))
+}
@@ -0,0 +1,88 @@
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use pyo3::prelude::*;
+
+use crate::error::SledClosedError;
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // the guarded values stay consistent even if a panic happened while they were locked
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn closed_error() -> PyErr {
+    SledClosedError::new_err("The database has been closed")
+}
+
+/// Something handed out by a database that holds on to sled's resources until it is closed.
+trait Close: Send + Sync {
+    fn close(&self);
+}
+
+/// Holds a `Db`, `Tree` or `Iter` until the database it came from is closed.
+pub(crate) struct Slot<T> {
+    value: Mutex<Option<T>>,
+}
+
+impl<T: Send> Close for Slot<T> {
+    fn close(&self) {
+        let value = lock(&self.value).take();
+        drop(value);
+    }
+}
+
+impl<T> Slot<T> {
+    /// Locks the slot, which is empty once the database is closed.
+    pub(crate) fn lock(&self) -> MutexGuard<'_, Option<T>> {
+        lock(&self.value)
+    }
+}
+
+impl<T: Clone> Slot<T> {
+    pub(crate) fn get(&self) -> PyResult<T> {
+        self.lock().clone().ok_or_else(closed_error)
+    }
+}
+
+/// Tracks everything opened from one database, so that closing it really lets go of sled's
+/// files even while Python still references trees or iterators.
+pub(crate) struct Registry {
+    // `None` once closed
+    handles: Mutex<Option<Vec<Weak<dyn Close>>>>,
+}
+
+impl Registry {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            handles: Mutex::new(Some(vec![])),
+        })
+    }
+
+    /// Puts `value` into a slot that is emptied when the database is closed.
+    pub(crate) fn slot<T: Send + 'static>(&self, value: T) -> Arc<Slot<T>> {
+        let mut handles = lock(&self.handles);
+        let slot = Arc::new(Slot {
+            value: Mutex::new(handles.is_some().then_some(value)),
+        });
+        if let Some(handles) = handles.as_mut() {
+            if handles.len() == handles.capacity() {
+                handles.retain(|handle| handle.strong_count() > 0);
+            }
+            let handle: Arc<dyn Close> = slot.clone();
+            handles.push(Arc::downgrade(&handle));
+        }
+        slot
+    }
+
+    /// Empties every slot. Returns `false` if the database was already closed.
+    pub(crate) fn close(&self) -> bool {
+        let handles = lock(&self.handles).take();
+        let handles = match handles {
+            Some(handles) => handles,
+            None => return false,
+        };
+        for handle in handles.iter().filter_map(Weak::upgrade) {
+            handle.close();
+        }
+        true
+    }
+}
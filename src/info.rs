@@ -0,0 +1,25 @@
+use pyo3::{prelude::*, types::PyDict};
+
+pub(crate) const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub(crate) const SLED_VERSION: &str = env!("PYSLED_SLED_VERSION");
+
+/// The cargo features this build was compiled with.
+const FEATURES: &[(&str, bool)] = &[("compression", cfg!(feature = "compression"))];
+
+/// Describes how this module was built: `version`, `sled_version`, the enabled cargo `features`,
+/// the `target` triple and whether it is a `debug` build.
+#[pyfunction]
+pub fn build_info(py: Python<'_>) -> PyResult<&PyDict> {
+    let info = PyDict::new(py);
+    info.set_item("version", VERSION)?;
+    info.set_item("sled_version", SLED_VERSION)?;
+    let features: Vec<&str> = FEATURES
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| *name)
+        .collect();
+    info.set_item("features", features)?;
+    info.set_item("target", env!("PYSLED_TARGET"))?;
+    info.set_item("debug", cfg!(debug_assertions))?;
+    Ok(info)
+}
@@ -0,0 +1,133 @@
+use std::{ops::Bound, sync::Arc};
+
+use pyo3::{prelude::*, types::PyBytes};
+use sled::{Iter, Tree};
+
+use crate::{
+    codec::Codecs,
+    convert_to_pyresult,
+    handle::{closed_error, Registry, Slot},
+};
+
+#[derive(Clone, Copy)]
+pub(crate) enum IterKind {
+    Items,
+    Keys,
+    Values,
+    /// `(key, value)` pairs with the values as `bytes` too, which `import_` takes back unchanged.
+    RawItems,
+}
+
+/// A lazy iterator over a tree, yielding `(key, value)` pairs, keys or values depending on how
+/// it was created. Keys are `bytes`. Use `reversed()` to walk the remaining entries from the
+/// back.
+#[pyclass]
+pub struct SledIter {
+    // `None` once exhausted, and the slot is emptied when the database is closed
+    inner: Option<Arc<Slot<Iter>>>,
+    kind: IterKind,
+    reverse: bool,
+    // set for iterators over a typed view, which yield decoded keys and values
+    codecs: Option<Arc<Codecs>>,
+}
+
+impl SledIter {
+    pub(crate) fn new(registry: &Registry, inner: Iter, kind: IterKind) -> Self {
+        Self {
+            inner: Some(registry.slot(inner)),
+            kind,
+            reverse: false,
+            codecs: None,
+        }
+    }
+
+    pub(crate) fn with_codecs(mut self, codecs: Arc<Codecs>) -> Self {
+        self.codecs = Some(codecs);
+        self
+    }
+}
+
+#[pymethods]
+impl SledIter {
+    pub fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __next__(&mut self, py: Python) -> PyResult<Option<PyObject>> {
+        let inner = match self.inner.as_ref() {
+            Some(inner) => inner,
+            None => return Ok(None),
+        };
+        let reverse = self.reverse;
+        let next = py.allow_threads(|| {
+            let mut inner = inner.lock();
+            let inner = inner.as_mut()?;
+            Some(if reverse {
+                inner.next_back()
+            } else {
+                inner.next()
+            })
+        });
+        let next = next.ok_or_else(closed_error)?;
+        let (k, v) = match next {
+            Some(e) => convert_to_pyresult(e)?,
+            None => {
+                self.inner = None;
+                return Ok(None);
+            }
+        };
+        if let Some(codecs) = &self.codecs {
+            return Ok(Some(match self.kind {
+                IterKind::Items | IterKind::RawItems => {
+                    (codecs.decode_key(py, &k)?, codecs.decode_value(py, &v)?).into_py(py)
+                }
+                IterKind::Keys => codecs.decode_key(py, &k)?,
+                IterKind::Values => codecs.decode_value(py, &v)?,
+            }));
+        }
+        // keys are bytes so they can be hashed and passed back as keys
+        Ok(Some(match self.kind {
+            IterKind::Items => (PyBytes::new(py, &k), v.to_vec()).into_py(py),
+            IterKind::Keys => PyBytes::new(py, &k).into_py(py),
+            IterKind::Values => v.to_vec().into_py(py),
+            IterKind::RawItems => (PyBytes::new(py, &k), PyBytes::new(py, &v)).into_py(py),
+        }))
+    }
+
+    /// Takes over the remaining entries of this iterator and yields them in the opposite order.
+    pub fn __reversed__(&mut self) -> Self {
+        Self {
+            inner: self.inner.take(),
+            kind: self.kind,
+            reverse: !self.reverse,
+            codecs: self.codecs.clone(),
+        }
+    }
+}
+
+pub(crate) fn range(
+    registry: &Registry,
+    tree: &Tree,
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    inclusive: bool,
+) -> SledIter {
+    let lo = match start {
+        Some(start) => Bound::Included(start),
+        None => Bound::Unbounded,
+    };
+    let hi = match end {
+        Some(end) if inclusive => Bound::Included(end),
+        Some(end) => Bound::Excluded(end),
+        None => Bound::Unbounded,
+    };
+    SledIter::new(registry, tree.range::<&[u8], _>((lo, hi)), IterKind::Items)
+}
+
+pub(crate) fn scan_prefix(registry: &Registry, tree: &Tree, prefix: &[u8]) -> SledIter {
+    SledIter::new(registry, tree.scan_prefix(prefix), IterKind::Items)
+}
+
+pub(crate) fn view(registry: &Registry, tree: &Tree, kind: IterKind) -> SledIter {
+    SledIter::new(registry, tree.iter(), kind)
+}
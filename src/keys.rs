@@ -0,0 +1,502 @@
+use pyo3::{
+    exceptions::{PyOverflowError, PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyBytes, PyFloat, PyLong, PyString, PyTuple},
+};
+
+use crate::bytes::Bytes;
+
+const NULL: u8 = 0x00;
+const BYTES: u8 = 0x01;
+const STRING: u8 = 0x02;
+const NESTED: u8 = 0x05;
+// integers take the codes 0x0c to 0x1c, 0x14 plus or minus their length in bytes
+const INT_ZERO: u8 = 0x14;
+const DOUBLE: u8 = 0x21;
+const FALSE: u8 = 0x26;
+const TRUE: u8 = 0x27;
+// follows a 0x00 byte inside bytes, strings and nested tuples to tell it apart from a terminator
+const ESCAPE: u8 = 0xff;
+
+// deeper nesting is rejected instead of risking a stack overflow, also for keys read from disk
+const MAX_DEPTH: usize = 256;
+
+/// One item of a packed tuple.
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Null,
+    Bytes(Vec<u8>),
+    Str(String),
+    Nested(Vec<Item>),
+    /// Within ±(2**64 - 1).
+    Int(i128),
+    Double(f64),
+    Bool(bool),
+}
+
+impl Item {
+    fn from_py(item: &PyAny, depth: usize) -> PyResult<Self> {
+        Ok(if item.is_none() {
+            Item::Null
+        } else if let Ok(b) = item.downcast::<PyBool>() {
+            Item::Bool(b.is_true())
+        } else if item.is_instance_of::<PyLong>()? {
+            let value: i128 = item.extract()?;
+            if u64::try_from(value.unsigned_abs()).is_err() {
+                return Err(PyOverflowError::new_err(
+                    "integers in keys must be within ±(2**64 - 1)",
+                ));
+            }
+            Item::Int(value)
+        } else if let Ok(f) = item.downcast::<PyFloat>() {
+            Item::Double(f.value())
+        } else if let Ok(b) = item.downcast::<PyBytes>() {
+            Item::Bytes(b.as_bytes().to_vec())
+        } else if let Ok(s) = item.downcast::<PyString>() {
+            Item::Str(s.to_str()?.to_owned())
+        } else if let Ok(t) = item.downcast::<PyTuple>() {
+            if depth == MAX_DEPTH {
+                return Err(PyValueError::new_err("the key is nested too deeply"));
+            }
+            Item::Nested(
+                t.iter()
+                    .map(|item| Item::from_py(item, depth + 1))
+                    .collect::<PyResult<_>>()?,
+            )
+        } else {
+            return Err(PyTypeError::new_err(format!(
+                "Cannot pack {} into a key",
+                item.get_type().name()?
+            )));
+        })
+    }
+
+    fn into_py(self, py: Python) -> PyObject {
+        match self {
+            Item::Null => py.None(),
+            Item::Bytes(b) => PyBytes::new(py, &b).into(),
+            Item::Str(s) => s.into_py(py),
+            Item::Nested(items) => {
+                PyTuple::new(py, items.into_iter().map(|item| item.into_py(py))).into()
+            }
+            Item::Int(value) => value.into_py(py),
+            Item::Double(value) => value.into_py(py),
+            Item::Bool(value) => value.into_py(py),
+        }
+    }
+}
+
+fn encode_escaped(out: &mut Vec<u8>, code: u8, bytes: &[u8]) {
+    out.push(code);
+    for &b in bytes {
+        out.push(b);
+        if b == 0x00 {
+            out.push(ESCAPE);
+        }
+    }
+    out.push(0x00);
+}
+
+fn encode_int(out: &mut Vec<u8>, value: i128) {
+    let magnitude = value.unsigned_abs() as u64;
+    let len = 8 - magnitude.leading_zeros() as usize / 8;
+    if value >= 0 {
+        out.push(INT_ZERO + len as u8);
+        out.extend_from_slice(&magnitude.to_be_bytes()[8 - len..]);
+    } else {
+        // the one's complement of the magnitude keeps negative numbers of one length in order
+        out.push(INT_ZERO - len as u8);
+        out.extend_from_slice(&(!magnitude).to_be_bytes()[8 - len..]);
+    }
+}
+
+fn encode(out: &mut Vec<u8>, item: &Item, nested: bool) {
+    match item {
+        Item::Null => {
+            out.push(NULL);
+            if nested {
+                out.push(ESCAPE);
+            }
+        }
+        Item::Bool(b) => out.push(if *b { TRUE } else { FALSE }),
+        Item::Int(value) => encode_int(out, *value),
+        Item::Double(value) => {
+            let bits = value.to_bits();
+            // flipping makes the IEEE 754 bit patterns sort numerically
+            let bits = if bits >> 63 == 1 {
+                !bits
+            } else {
+                bits ^ 1 << 63
+            };
+            out.push(DOUBLE);
+            out.extend_from_slice(&bits.to_be_bytes());
+        }
+        Item::Bytes(b) => encode_escaped(out, BYTES, b),
+        Item::Str(s) => encode_escaped(out, STRING, s.as_bytes()),
+        Item::Nested(items) => {
+            out.push(NESTED);
+            for item in items {
+                encode(out, item, true);
+            }
+            out.push(0x00);
+        }
+    }
+}
+
+fn pack_items(items: &[Item]) -> Vec<u8> {
+    let mut out = vec![];
+    for item in items {
+        encode(&mut out, item, false);
+    }
+    out
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+const TRUNCATED: &str = "Packed key is truncated";
+
+impl Decoder<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        let bytes = self.data.get(self.pos..self.pos + len).ok_or(TRUNCATED)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.data.get(self.pos + offset).copied()
+    }
+
+    // a 0x00 that is not followed by the escape byte
+    fn at_terminator(&self) -> bool {
+        self.peek(0) == Some(0x00) && self.peek(1) != Some(ESCAPE)
+    }
+
+    fn escaped(&mut self) -> Result<Vec<u8>, String> {
+        let mut bytes = vec![];
+        loop {
+            if self.at_terminator() {
+                self.pos += 1;
+                return Ok(bytes);
+            }
+            let b = self.take(1)?[0];
+            if b == 0x00 {
+                self.pos += 1;
+            }
+            bytes.push(b);
+        }
+    }
+
+    fn uint(&mut self, len: usize) -> Result<u64, String> {
+        let mut buf = [0; 8];
+        buf[8 - len..].copy_from_slice(self.take(len)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    /// Items directly in the key are at depth 0, items of a nested tuple one deeper.
+    fn item(&mut self, depth: usize) -> Result<Item, String> {
+        let code = self.take(1)?[0];
+        Ok(match code {
+            NULL => {
+                if depth > 0 {
+                    self.take(1)?;
+                }
+                Item::Null
+            }
+            BYTES => Item::Bytes(self.escaped()?),
+            STRING => Item::Str(String::from_utf8(self.escaped()?).map_err(|e| e.to_string())?),
+            NESTED => {
+                if depth == MAX_DEPTH {
+                    return Err("Packed key is nested too deeply".to_owned());
+                }
+                let mut items = vec![];
+                while !self.at_terminator() {
+                    items.push(self.item(depth + 1)?);
+                }
+                self.pos += 1;
+                Item::Nested(items)
+            }
+            0x0c..=0x1c => {
+                let len = code.abs_diff(INT_ZERO) as usize;
+                let value = self.uint(len)?;
+                if code >= INT_ZERO {
+                    Item::Int(value.into())
+                } else {
+                    let mask = u64::MAX >> (64 - 8 * len);
+                    Item::Int(-i128::from(!value & mask))
+                }
+            }
+            DOUBLE => {
+                let bits = self.uint(8)?;
+                let bits = if bits >> 63 == 1 {
+                    bits ^ 1 << 63
+                } else {
+                    !bits
+                };
+                Item::Double(f64::from_bits(bits))
+            }
+            FALSE => Item::Bool(false),
+            TRUE => Item::Bool(true),
+            other => return Err(format!("Unknown type code {:#04x} in packed key", other)),
+        })
+    }
+}
+
+fn unpack_items(key: &[u8]) -> Result<Vec<Item>, String> {
+    let mut decoder = Decoder { data: key, pos: 0 };
+    let mut items = vec![];
+    while decoder.pos < key.len() {
+        items.push(decoder.item(0)?);
+    }
+    Ok(items)
+}
+
+/// The keys enclosing every packed tuple that extends the packed `prefix`.
+fn range_bounds(prefix: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    ([prefix, &[0x00]].concat(), [prefix, &[0xff]].concat())
+}
+
+/// Packs a tuple of `None`, `bool`, `int`, `float`, `str`, `bytes` and nested tuples into a key.
+/// Packed keys sort like the tuples themselves, with values of different types ordered by type.
+#[pyfunction]
+pub fn pack<'p>(py: Python<'p>, items: &PyTuple) -> PyResult<&'p PyBytes> {
+    let items = items
+        .iter()
+        .map(|item| Item::from_py(item, 0))
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(PyBytes::new(py, &pack_items(&items)))
+}
+
+/// Turns a key created by `pack` back into a tuple.
+#[pyfunction]
+pub fn unpack<'p>(py: Python<'p>, key: Bytes) -> PyResult<&'p PyTuple> {
+    let items = unpack_items(&key).map_err(PyValueError::new_err)?;
+    Ok(PyTuple::new(
+        py,
+        items.into_iter().map(|item| item.into_py(py)),
+    ))
+}
+
+/// The `(start, end)` keys enclosing every packed tuple that extends `prefix`, for use with
+/// `range(start, end)`. The prefix itself is not included. Unlike a byte prefix scan over
+/// `pack(prefix)`, this leaves out tuples that only share bytes with it, such as `(b"a\x00",)`
+/// for the prefix `(b"a",)`.
+#[pyfunction]
+pub fn range_for<'p>(py: Python<'p>, prefix: &PyTuple) -> PyResult<(&'p PyBytes, &'p PyBytes)> {
+    let (start, end) = range_bounds(pack(py, prefix)?.as_bytes());
+    Ok((PyBytes::new(py, &start), PyBytes::new(py, &end)))
+}
+
+/// Creates the `pysled.keys` submodule.
+pub(crate) fn module(py: Python<'_>) -> PyResult<&PyModule> {
+    let m = PyModule::new(py, "keys")?;
+    m.add(
+        "__doc__",
+        "Order-preserving encoding of tuples into keys, compatible with FoundationDB's tuple \
+         layer for the supported types.",
+    )?;
+    m.add_function(wrap_pyfunction!(pack, m)?)?;
+    m.add_function(wrap_pyfunction!(unpack, m)?)?;
+    m.add_function(wrap_pyfunction!(range_for, m)?)?;
+    Ok(m)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTS: &[i128] = &[
+        -(u64::MAX as i128),
+        -(1 << 32),
+        -65536,
+        -65535,
+        -256,
+        -255,
+        -1,
+        0,
+        1,
+        255,
+        256,
+        65535,
+        65536,
+        1 << 32,
+        u64::MAX as i128,
+    ];
+
+    fn assert_sorted(items: &[Vec<Item>]) {
+        for pair in items.windows(2) {
+            assert!(
+                pack_items(&pair[0]) < pack_items(&pair[1]),
+                "{:?} does not sort before {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    fn nested(depth: usize) -> Item {
+        (0..depth).fold(Item::Null, |inner, _| Item::Nested(vec![inner]))
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut items: Vec<Item> = INTS.iter().map(|&i| Item::Int(i)).collect();
+        items.extend(
+            [
+                0.0,
+                -0.0,
+                1.5,
+                -1.5,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::MIN_POSITIVE,
+            ]
+            .map(Item::Double),
+        );
+        items.extend([
+            Item::Null,
+            Item::Bool(false),
+            Item::Bool(true),
+            Item::Bytes(vec![]),
+            Item::Bytes(vec![0x00, 0xff, 0x00, 0x00]),
+            Item::Str("a\0b".to_owned()),
+            Item::Str("ünïcode".to_owned()),
+            Item::Nested(vec![]),
+            Item::Nested(vec![Item::Null, Item::Bytes(vec![0x00]), Item::Null]),
+            Item::Nested(vec![Item::Nested(vec![Item::Null]), Item::Int(-1)]),
+            nested(MAX_DEPTH),
+        ]);
+        for item in &items {
+            let packed = pack_items(std::slice::from_ref(item));
+            assert_eq!(unpack_items(&packed).unwrap(), vec![item.clone()]);
+        }
+        assert_eq!(unpack_items(&pack_items(&items)).unwrap(), items);
+    }
+
+    #[test]
+    fn round_trip_keeps_signed_zero_and_nan() {
+        for value in [0.0, -0.0, f64::NAN, -f64::NAN] {
+            let unpacked = unpack_items(&pack_items(&[Item::Double(value)])).unwrap();
+            match unpacked[..] {
+                [Item::Double(v)] => assert_eq!(v.to_bits(), value.to_bits()),
+                _ => panic!("unpacked {:?}", unpacked),
+            }
+        }
+    }
+
+    #[test]
+    fn ints_sort_across_lengths() {
+        let ints: Vec<_> = INTS.iter().map(|&i| vec![Item::Int(i)]).collect();
+        assert_sorted(&ints);
+    }
+
+    #[test]
+    fn doubles_sort_numerically() {
+        let doubles: Vec<_> = [
+            -f64::NAN,
+            f64::NEG_INFINITY,
+            -1.5,
+            -f64::MIN_POSITIVE,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.5,
+            f64::INFINITY,
+            f64::NAN,
+        ]
+        .map(|d| vec![Item::Double(d)])
+        .into();
+        assert_sorted(&doubles);
+    }
+
+    #[test]
+    fn embedded_zeros_sort_like_bytes() {
+        let bytes: Vec<_> = [&[][..], &[0x00], &[0x00, 0x00], &[0x00, 0x01], &[0x01]]
+            .map(|b| vec![Item::Bytes(b.to_vec())])
+            .into();
+        assert_sorted(&bytes);
+    }
+
+    #[test]
+    fn tuples_sort_by_item_then_length() {
+        assert_sorted(&[
+            vec![],
+            vec![Item::Null],
+            vec![Item::Null, Item::Null],
+            vec![Item::Bytes(vec![])],
+            vec![Item::Str(String::new())],
+            vec![Item::Nested(vec![])],
+            vec![Item::Nested(vec![Item::Null])],
+            vec![Item::Nested(vec![Item::Null, Item::Null])],
+            vec![Item::Nested(vec![Item::Int(0)])],
+            vec![Item::Int(1)],
+            vec![Item::Int(1), Item::Null],
+            vec![Item::Int(1), Item::Int(0)],
+            vec![Item::Int(2)],
+            vec![Item::Double(0.0)],
+            vec![Item::Bool(false)],
+            vec![Item::Bool(true)],
+        ]);
+    }
+
+    #[test]
+    fn range_bounds_enclose_extensions_only() {
+        let prefix = [Item::Int(7), Item::Nested(vec![Item::Null])];
+        let (start, end) = range_bounds(&pack_items(&prefix));
+        let inside = [
+            Item::Null,
+            Item::Bytes(vec![0x00]),
+            Item::Int(-(u64::MAX as i128)),
+            Item::Double(f64::NAN),
+            Item::Bool(true),
+        ];
+        for item in inside {
+            let key = pack_items(&[prefix[0].clone(), prefix[1].clone(), item]);
+            assert!(start <= key && key < end);
+        }
+        let outside = [
+            prefix.to_vec(),
+            vec![Item::Int(7)],
+            vec![Item::Int(7), Item::Nested(vec![])],
+            vec![Item::Int(7), Item::Nested(vec![Item::Null, Item::Null])],
+            vec![Item::Int(8)],
+        ];
+        for items in outside {
+            let key = pack_items(&items);
+            assert!(key < start || key >= end, "{:?} is in range", items);
+        }
+    }
+
+    #[test]
+    fn range_bounds_leave_out_keys_only_sharing_bytes() {
+        let prefix = pack_items(&[Item::Bytes(b"a".to_vec())]);
+        let longer = pack_items(&[Item::Bytes(b"a\x00".to_vec())]);
+        assert!(longer.starts_with(&prefix));
+        let (start, end) = range_bounds(&prefix);
+        assert!(longer < start || longer >= end);
+    }
+
+    #[test]
+    fn unpack_rejects_deep_nesting() {
+        assert!(unpack_items(&pack_items(&[nested(MAX_DEPTH)])).is_ok());
+        assert_eq!(
+            unpack_items(&pack_items(&[nested(MAX_DEPTH + 1)])),
+            Err("Packed key is nested too deeply".to_owned())
+        );
+        assert!(unpack_items(&vec![NESTED; 500_000]).is_err());
+    }
+
+    #[test]
+    fn unpack_rejects_malformed_keys() {
+        assert_eq!(unpack_items(&[BYTES, b'a']), Err(TRUNCATED.to_owned()));
+        assert_eq!(unpack_items(&[INT_ZERO + 2, 1]), Err(TRUNCATED.to_owned()));
+        assert_eq!(unpack_items(&[NESTED]), Err(TRUNCATED.to_owned()));
+        assert!(unpack_items(&[STRING, 0xc3, 0x28, 0x00]).is_err());
+        assert_eq!(
+            unpack_items(&[0x40]),
+            Err("Unknown type code 0x40 in packed key".to_owned())
+        );
+    }
+}
@@ -1,232 +1,699 @@
-use std::path::PathBuf;
+#![allow(non_local_definitions, unexpected_cfgs)]
 
-use pyo3::{exceptions::PyValueError, prelude::*};
+use std::{path::PathBuf, sync::Arc};
+
+use pyo3::{
+    basic::CompareOp,
+    exceptions::{PyOverflowError, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict, PyIterator, PyList, PyTuple},
+};
 use sled::{Db, Tree};
 
+mod asyncio;
+mod batch;
+mod bytes;
+mod cas;
+mod codec;
+mod config;
+mod error;
+mod export;
+mod handle;
+mod info;
+mod iter;
+mod keys;
+mod mapping;
+mod merge;
+mod ordered;
+mod subscriber;
+mod transaction;
+mod typed;
+mod value;
+mod view;
+
+use batch::SledBatch;
+use bytes::Bytes;
+use codec::{Codec, Codecs, PickleCodec};
+use config::SledConfig;
+use error::{
+    CodecError, CollectionNotFound, CompareAndSwapError, Corruption, ReportableBug,
+    SledClosedError, SledError, Unsupported,
+};
+use handle::{Registry, Slot};
+use iter::{IterKind, SledIter};
+use ordered::Entry;
+use subscriber::{InsertEvent, RemoveEvent, SledSubscriber};
+use transaction::SledTransactionalTree;
+use typed::SledTypedTree;
+use value::SledValue;
+use view::{SledItemsView, SledKeysView, SledValuesView, ViewSource};
+
 fn convert_to_pyresult<T>(inp: sled::Result<T>) -> PyResult<T> {
-    inp.map_err(|e| PyValueError::new_err(e.to_string()))
+    inp.map_err(|e| Python::with_gil(|py| error::to_pyerr(py, e)))
 }
 
-#[pyclass]
-pub struct CompareAndSwapError {
-    #[pyo3(get, set)]
-    pub current: Option<Vec<u8>>,
-    #[pyo3(get, set)]
-    pub proposed: Option<Vec<u8>>,
+/// A database, which is also a `SledTree` holding its default tree. Everything a tree can do is
+/// inherited from `SledTree`, so both always offer the same operations.
+#[pyclass(extends = SledTree, mapping)]
+pub struct SledDb {
+    inner: Arc<Slot<Db>>,
+    // everything opened from this database, so that `close` can let go of it
+    registry: Arc<Registry>,
 }
 
-#[pyclass]
-pub struct SledDb {
-    inner: Db,
+impl SledDb {
+    pub(crate) fn init(db: Db) -> PyClassInitializer<Self> {
+        let registry = Registry::new();
+        let default_tree = SledTree {
+            inner: registry.slot((*db).clone()),
+            registry: registry.clone(),
+        };
+        PyClassInitializer::from(default_tree).add_subclass(Self {
+            inner: registry.slot(db),
+            registry,
+        })
+    }
+
+    pub(crate) fn db(&self) -> PyResult<Db> {
+        self.inner.get()
+    }
+
+    fn wrap_tree(&self, tree: Tree) -> SledTree {
+        SledTree {
+            inner: self.registry.slot(tree),
+            registry: self.registry.clone(),
+        }
+    }
 }
 
 #[pymethods]
 impl SledDb {
     #[new]
-    pub fn new(path: PathBuf) -> PyResult<Self> {
-        let inner = sled::open(&path)
-            .map_err(|e| PyValueError::new_err(format!("Failed to open db: {}", e.to_string())))?;
-        Ok(Self { inner })
+    #[args(
+        "*",
+        cache_capacity = "None",
+        mode = "None",
+        use_compression = "None",
+        compression_factor = "None"
+    )]
+    pub fn new(
+        py: Python,
+        path: PathBuf,
+        cache_capacity: Option<u64>,
+        mode: Option<&str>,
+        use_compression: Option<bool>,
+        compression_factor: Option<i32>,
+    ) -> PyResult<PyClassInitializer<Self>> {
+        let mut config = sled::Config::new().path(path);
+        if let Some(cache_capacity) = cache_capacity {
+            config = config.cache_capacity(cache_capacity);
+        }
+        if let Some(mode) = mode {
+            config = config.mode(config::parse_mode(mode)?);
+        }
+        if let Some(use_compression) = use_compression {
+            config = config.use_compression(use_compression);
+        }
+        if let Some(compression_factor) = compression_factor {
+            config = config.compression_factor(compression_factor);
+        }
+        config::open_config(py, &config)
+    }
+
+    /// Opens a database that is deleted once it is closed or garbage collected. Without a
+    /// `path` it is placed in `/dev/shm` on Linux and the temp directory elsewhere.
+    #[staticmethod]
+    #[args(path = "None")]
+    pub fn temporary(py: Python, path: Option<PathBuf>) -> PyResult<Py<Self>> {
+        let mut config = sled::Config::new().temporary(true);
+        if let Some(path) = path {
+            config = config.path(path);
+        }
+        Py::new(py, config::open_config(py, &config)?)
+    }
+
+    /// Opens a temporary database that never flushes in the background. On Linux it lives in
+    /// `/dev/shm`, so nothing touches the disk.
+    #[staticmethod]
+    pub fn in_memory(py: Python) -> PyResult<Py<Self>> {
+        let config = sled::Config::new().temporary(true).flush_every_ms(None);
+        Py::new(py, config::open_config(py, &config)?)
     }
 
-    pub fn insert(&self, key: &[u8], value: Vec<u8>) -> PyResult<Option<Vec<u8>>> {
-        convert_to_pyresult(self.inner.insert(key, value)).map(|o| o.map(|i| i.to_vec()))
+    /// A checksum over every tree of the database, where `SledTree.checksum` covers one tree.
+    pub fn checksum(&self, py: Python) -> PyResult<u32> {
+        let db = self.db()?;
+        convert_to_pyresult(py.allow_threads(|| db.checksum()))
     }
 
-    pub fn get(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
-        convert_to_pyresult(self.inner.get(key)).map(|o| o.map(|i| i.to_vec()))
+    /// Returns a `SledTree`, or a `SledTypedTree` if a `key_codec` or `value_codec` is given.
+    /// A codec is either `"bytes"`, `"str"`, `"int"`, `"json"`, `"msgpack"`, `"pickle"`, or an
+    /// object with `encode` and `decode` methods such as `PickleCodec`.
+    #[args(key_codec = "None", value_codec = "None")]
+    pub fn open_tree(
+        &self,
+        py: Python,
+        name: Bytes,
+        key_codec: Option<&PyAny>,
+        value_codec: Option<&PyAny>,
+    ) -> PyResult<PyObject> {
+        let db = self.db()?;
+        let tree = convert_to_pyresult(py.allow_threads(|| db.open_tree(name)))?;
+        let tree = self.wrap_tree(tree);
+        if key_codec.is_none() && value_codec.is_none() {
+            return Ok(tree.into_py(py));
+        }
+        let codecs = Codecs {
+            key: Codec::from_py(py, key_codec)?,
+            value: Codec::from_py(py, value_codec)?,
+        };
+        Ok(SledTypedTree::new(tree, codecs).into_py(py))
+    }
+
+    pub fn drop_tree(&self, py: Python, name: Bytes) -> PyResult<bool> {
+        let db = self.db()?;
+        convert_to_pyresult(py.allow_threads(|| db.drop_tree(name)))
+    }
+
+    /// The names of all trees, including the default one.
+    pub fn tree_names<'p>(&self, py: Python<'p>) -> PyResult<Vec<&'p PyBytes>> {
+        let db = self.db()?;
+        Ok(db
+            .tree_names()
+            .iter()
+            .map(|name| PyBytes::new(py, name))
+            .collect())
+    }
+
+    /// Iterates over the names of all trees, including the default one. Iterating the database
+    /// itself yields the keys of its default tree, like any mapping.
+    pub fn iter_tree_names<'p>(&self, py: Python<'p>) -> PyResult<&'p PyIterator> {
+        PyIterator::from_object(py, PyList::new(py, self.tree_names(py)?))
+    }
+
+    /// All trees, including the default one.
+    pub fn trees(&self, py: Python) -> PyResult<Vec<SledTree>> {
+        let db = self.db()?;
+        convert_to_pyresult(py.allow_threads(|| {
+            db.tree_names()
+                .into_iter()
+                .map(|name| db.open_tree(name))
+                .collect::<sled::Result<Vec<_>>>()
+        }))
+        .map(|trees| trees.into_iter().map(|tree| self.wrap_tree(tree)).collect())
+    }
+
+    /// Whether the database existed before and was recovered from disk.
+    pub fn was_recovered(&self) -> PyResult<bool> {
+        let db = self.db()?;
+        Ok(db.was_recovered())
+    }
+
+    /// Maps every tree name to its number of keys. Each tree is counted by a full scan at a
+    /// slightly different moment, so under concurrent writes the counts are approximate.
+    pub fn tree_stats<'p>(&self, py: Python<'p>) -> PyResult<&'p PyDict> {
+        let db = self.db()?;
+        let stats = PyDict::new(py);
+        for name in db.tree_names() {
+            let len = convert_to_pyresult(
+                py.allow_threads(|| db.open_tree(&name).map(|tree| tree.len())),
+            )?;
+            stats.set_item(PyBytes::new(py, &name), len)?;
+        }
+        Ok(stats)
     }
 
-    pub fn remove(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
-        convert_to_pyresult(self.inner.remove(key)).map(|o| o.map(|i| i.to_vec()))
+    pub fn size_on_disk(&self, py: Python) -> PyResult<u64> {
+        let db = self.db()?;
+        convert_to_pyresult(py.allow_threads(|| db.size_on_disk()))
     }
 
-    pub fn clear(&self) -> PyResult<()> {
-        convert_to_pyresult(self.inner.clear())
+    /// All collections as `(collection_type, name, items)` tuples, with `items` iterating over
+    /// the `(key, value)` pairs. Together with `import_` this can move data between databases
+    /// written by different sled versions.
+    pub fn export(&self, py: Python) -> PyResult<Vec<(PyObject, PyObject, SledIter)>> {
+        let db = self.db()?;
+        export::export(py, &self.registry, &db)
     }
 
-    pub fn all(&self) -> PyResult<Vec<(Vec<u8>, Vec<u8>)>> {
-        let mut out = Vec::new();
-        let iter = self.inner.iter();
-        out.reserve(iter.size_hint().0);
-        for e in iter {
-            let (a, b) = convert_to_pyresult(e)?;
-            out.push((a.to_vec(), b.to_vec()));
+    /// Imports the output of `export`. The trees it creates must not contain data yet. Nothing is
+    /// kept if the import fails part way.
+    pub fn import_(&self, py: Python, collections: &PyAny) -> PyResult<()> {
+        let db = self.db()?;
+        export::import(py, &db, collections)
+    }
+
+    /// Writes all collections to a new file at `path`, in a versioned format that `load_from`
+    /// of this and later pysled releases can read. Raises `IoError` if `path` already exists.
+    pub fn dump_to(&self, py: Python, path: PathBuf) -> PyResult<()> {
+        let db = self.db()?;
+        export::dump_to(py, &db, &path)
+    }
+
+    /// Imports a file written by `dump_to`. The trees it creates must not contain data yet.
+    /// Nothing is kept if the file is truncated or corrupt.
+    pub fn load_from(&self, py: Python, path: PathBuf) -> PyResult<()> {
+        let db = self.db()?;
+        export::load_from(py, &db, &path)
+    }
+
+    /// Flushes and closes the database. Its files are released once operations still running in
+    /// other threads are done. Any later use of the database, or of trees and iterators opened
+    /// from it, raises `SledClosedError`. Closing an already closed database does nothing.
+    pub fn close(&self, py: Python) -> PyResult<()> {
+        let db = match self.inner.lock().clone() {
+            Some(db) => db,
+            None => return Ok(()),
+        };
+        convert_to_pyresult(py.allow_threads(|| db.flush()))?;
+        py.allow_threads(|| {
+            self.registry.close();
+            drop(db);
+        });
+        Ok(())
+    }
+
+    pub fn __enter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __exit__(
+        &self,
+        py: Python,
+        _exc_type: &PyAny,
+        _exc_value: &PyAny,
+        _traceback: &PyAny,
+    ) -> PyResult<bool> {
+        self.close(py)?;
+        Ok(false)
+    }
+
+    /// A monotonically increasing id that is unique across restarts, though ids may be skipped
+    /// after a crash.
+    pub fn generate_id(&self, py: Python) -> PyResult<u64> {
+        let db = self.db()?;
+        convert_to_pyresult(py.allow_threads(|| db.generate_id()))
+    }
+
+    /// Like `generate_id`, but encoded as a big-endian key of `width` bytes, so the keys sort in
+    /// the order they were generated. Raises `OverflowError` once an id no longer fits.
+    #[args(width = "8")]
+    pub fn generate_key<'p>(&self, py: Python<'p>, width: usize) -> PyResult<&'p PyBytes> {
+        if width == 0 {
+            return Err(PyValueError::new_err("width must be at least 1"));
+        }
+        let id = self.generate_id(py)?.to_be_bytes();
+        let (padding, digits) = id.split_at(id.len().saturating_sub(width));
+        if padding.iter().any(|&b| b != 0) {
+            return Err(PyOverflowError::new_err(format!(
+                "id does not fit into {} bytes",
+                width
+            )));
         }
-        Ok(out)
+        let mut key = vec![0; width - digits.len()];
+        key.extend_from_slice(digits);
+        Ok(PyBytes::new(py, &key))
     }
+}
 
-    pub fn compare_and_swamp(
+#[pyclass(mapping, subclass)]
+#[derive(Clone)]
+pub struct SledTree {
+    inner: Arc<Slot<Tree>>,
+    registry: Arc<Registry>,
+}
+
+impl SledTree {
+    pub(crate) fn tree(&self) -> PyResult<Tree> {
+        self.inner.get()
+    }
+
+    fn view_source(slf: PyRef<Self>, py: Python) -> ViewSource {
+        let raw = (*slf).clone();
+        ViewSource::new(slf.into_py(py), raw, None)
+    }
+}
+
+#[pymethods]
+impl SledTree {
+    pub fn insert(&self, py: Python, key: Bytes, value: Bytes) -> PyResult<Option<Vec<u8>>> {
+        let tree = self.tree()?;
+        convert_to_pyresult(
+            py.allow_threads(|| tree.insert(key, value).map(|o| o.map(|i| i.to_vec()))),
+        )
+    }
+
+    /// Returns `default` if `key` is missing. With `raw=True` the value is returned as a
+    /// `SledValue` instead of being copied.
+    #[args(default = "None", "*", raw = "false")]
+    pub fn get(
         &self,
-        key: &[u8],
-        old: Option<&[u8]>,
-        new: Option<Vec<u8>>,
-    ) -> PyResult<Option<CompareAndSwapError>> {
-        convert_to_pyresult(self.inner.compare_and_swap(key, old, new)).map(|e| {
-            e.map_err(|i| CompareAndSwapError {
-                current: i.current.map(|e| e.to_vec()),
-                proposed: i.proposed.map(|e| e.to_vec()),
-            })
-            .err()
-        })
+        py: Python,
+        key: Bytes,
+        default: Option<PyObject>,
+        raw: bool,
+    ) -> PyResult<Option<PyObject>> {
+        let tree = self.tree()?;
+        let value = convert_to_pyresult(py.allow_threads(|| tree.get(key)))?
+            .map(|v| value::into_py(py, v, raw))
+            .transpose()?;
+        Ok(value.or(default))
     }
 
-    pub fn checksum(&self) -> PyResult<u32> {
-        convert_to_pyresult(self.inner.checksum())
+    pub fn remove(&self, py: Python, key: Bytes) -> PyResult<Option<Vec<u8>>> {
+        let tree = self.tree()?;
+        convert_to_pyresult(py.allow_threads(|| tree.remove(key).map(|o| o.map(|i| i.to_vec()))))
     }
 
-    pub fn flush(&self) -> PyResult<usize> {
-        convert_to_pyresult(self.inner.flush())
+    pub fn clear(&self, py: Python) -> PyResult<()> {
+        let tree = self.tree()?;
+        convert_to_pyresult(py.allow_threads(|| tree.clear()))
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+    pub fn all(&self, py: Python) -> PyResult<Vec<Entry>> {
+        let tree = self.tree()?;
+        let entries = convert_to_pyresult(
+            py.allow_threads(|| tree.iter().collect::<sled::Result<Vec<_>>>()),
+        )?;
+        Ok(entries.into_iter().map(|e| ordered::entry(py, e)).collect())
     }
 
-    pub fn __len__(&self) -> usize {
-        self.inner.len()
+    #[args(start = "None", end = "None", inclusive = "false")]
+    pub fn range(
+        &self,
+        start: Option<Bytes>,
+        end: Option<Bytes>,
+        inclusive: bool,
+    ) -> PyResult<SledIter> {
+        let tree = self.tree()?;
+        Ok(iter::range(
+            &self.registry,
+            &tree,
+            start.as_deref(),
+            end.as_deref(),
+            inclusive,
+        ))
     }
 
-    pub fn __contains__(&self, key: &[u8]) -> PyResult<bool> {
-        convert_to_pyresult(self.inner.contains_key(key))
+    pub fn scan_prefix(&self, prefix: Bytes) -> PyResult<SledIter> {
+        let tree = self.tree()?;
+        Ok(iter::scan_prefix(&self.registry, &tree, &prefix))
     }
 
-    pub fn __getitem__(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
-        self.get(key)
+    pub fn keys(slf: PyRef<Self>, py: Python) -> SledKeysView {
+        SledKeysView::new(Self::view_source(slf, py))
     }
 
-    pub fn __setitem__(&self, key: &[u8], value: Vec<u8>) -> PyResult<()> {
-        self.insert(key, value).map(|_| ())
+    pub fn values(slf: PyRef<Self>, py: Python) -> SledValuesView {
+        SledValuesView::new(Self::view_source(slf, py))
     }
 
-    pub fn __delitem__(&self, key: &[u8]) -> PyResult<()> {
-        self.remove(key).map(|_| ())
+    pub fn items(slf: PyRef<Self>, py: Python) -> SledItemsView {
+        SledItemsView::new(Self::view_source(slf, py))
     }
 
-    #[getter]
-    pub fn name(&self) -> Vec<u8> {
-        self.inner.name().to_vec()
+    /// The entry with the smallest key as a `(key, value)` tuple, or `None` if empty.
+    pub fn first(&self, py: Python) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::first(py, &tree)
     }
 
-    pub fn contains_key(&self, key: &[u8]) -> PyResult<bool> {
-        convert_to_pyresult(self.inner.contains_key(key))
+    /// The entry with the largest key as a `(key, value)` tuple, or `None` if empty.
+    pub fn last(&self, py: Python) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::last(py, &tree)
     }
 
-    pub fn len(&self) -> usize {
-        self.inner.len()
+    /// The entry with the largest key strictly below `key`.
+    pub fn get_lt(&self, py: Python, key: Bytes) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::get_lt(py, &tree, &key)
     }
 
-    pub fn open_tree(&self, name: &[u8]) -> PyResult<SledTree> {
-        convert_to_pyresult(self.inner.open_tree(name)).map(|e| SledTree { inner: e })
+    /// The entry with the smallest key strictly above `key`.
+    pub fn get_gt(&self, py: Python, key: Bytes) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::get_gt(py, &tree, &key)
     }
 
-    pub fn drop_tree(&self, name: &[u8]) -> PyResult<bool> {
-        convert_to_pyresult(self.inner.drop_tree(name))
+    /// The entry with the largest key less than or equal to `key`.
+    pub fn floor(&self, py: Python, key: Bytes) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::floor(py, &tree, &key)
     }
 
-    pub fn size_on_disk(&self) -> PyResult<u64> {
-        convert_to_pyresult(self.inner.size_on_disk())
+    /// The entry with the smallest key greater than or equal to `key`.
+    pub fn ceiling(&self, py: Python, key: Bytes) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::ceiling(py, &tree, &key)
     }
-}
 
-#[pyclass(mapping)]
-pub struct SledTree {
-    inner: Tree,
-}
+    /// Atomically removes and returns the entry with the smallest key.
+    pub fn pop_min(&self, py: Python) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::pop_min(py, &tree)
+    }
 
-#[pymethods]
-impl SledTree {
-    pub fn insert(&self, key: &[u8], value: Vec<u8>) -> PyResult<Option<Vec<u8>>> {
-        convert_to_pyresult(self.inner.insert(key, value)).map(|o| o.map(|i| i.to_vec()))
+    /// Atomically removes and returns the entry with the largest key.
+    pub fn pop_max(&self, py: Python) -> PyResult<Option<Entry>> {
+        let tree = self.tree()?;
+        ordered::pop_max(py, &tree)
     }
 
-    pub fn get(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
-        convert_to_pyresult(self.inner.get(key)).map(|o| o.map(|i| i.to_vec()))
+    /// Removes up to `n` entries with the smallest keys and returns them in key order. The entries
+    /// are removed in one transaction, so either all of them are popped or none.
+    pub fn pop_min_n(&self, py: Python, n: usize) -> PyResult<Vec<Entry>> {
+        let tree = self.tree()?;
+        ordered::pop_min_n(py, &tree, n)
     }
 
-    pub fn remove(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
-        convert_to_pyresult(self.inner.remove(key)).map(|o| o.map(|i| i.to_vec()))
+    pub fn transaction(&self, py: Python, f: &PyAny) -> PyResult<PyObject> {
+        let tree = self.tree()?;
+        transaction::run(py, std::slice::from_ref(&tree), f)
     }
 
-    pub fn clear(&self) -> PyResult<()> {
-        convert_to_pyresult(self.inner.clear())
+    pub fn apply_batch(&self, py: Python, batch: &SledBatch) -> PyResult<()> {
+        let tree = self.tree()?;
+        batch::apply_batch(py, &tree, batch)
     }
 
-    pub fn all(&self) -> PyResult<Vec<(Vec<u8>, Vec<u8>)>> {
-        let mut out = Vec::new();
-        let iter = self.inner.iter();
-        out.reserve(iter.size_hint().0);
-        for e in iter {
-            let (a, b) = convert_to_pyresult(e)?;
-            out.push((a.to_vec(), b.to_vec()));
-        }
-        Ok(out)
+    pub fn insert_many(&self, py: Python, pairs: &PyAny) -> PyResult<()> {
+        let tree = self.tree()?;
+        batch::insert_many(py, &tree, pairs)
+    }
+
+    pub fn set_merge_operator(&self, py: Python, operator: &PyAny) -> PyResult<()> {
+        let tree = self.tree()?;
+        merge::set_merge_operator(py, &tree, operator)
+    }
+
+    pub fn merge(&self, py: Python, key: Bytes, value: Bytes) -> PyResult<Option<Vec<u8>>> {
+        let tree = self.tree()?;
+        merge::merge(py, &tree, &key, &value)
+    }
+
+    pub fn watch_prefix(&self, prefix: Bytes) -> PyResult<SledSubscriber> {
+        let tree = self.tree()?;
+        Ok(subscriber::watch_prefix(&tree, &prefix))
+    }
+
+    /// Sets `key` to `new` if its current value is `old`, where `None` stands for a missing key.
+    /// Raises `CompareAndSwapError` if the value is different.
+    pub fn compare_and_swap(
+        &self,
+        py: Python,
+        key: Bytes,
+        old: Option<Bytes>,
+        new: Option<Bytes>,
+    ) -> PyResult<()> {
+        let tree = self.tree()?;
+        cas::compare_and_swap(py, &tree, &key, old.as_deref(), new.as_deref())
     }
 
+    /// Atomically replaces the value of `key` with `f(old)` and returns the new value. `f` takes
+    /// and returns `bytes` or `None`, and is called again if another writer changed the value
+    /// in the meantime.
+    pub fn update_and_fetch(&self, py: Python, key: Bytes, f: &PyAny) -> PyResult<Option<Vec<u8>>> {
+        let tree = self.tree()?;
+        cas::update_and_fetch(py, &tree, &key, f)
+    }
+
+    /// Like `update_and_fetch`, but returns the value from before the update.
+    pub fn fetch_and_update(&self, py: Python, key: Bytes, f: &PyAny) -> PyResult<Option<Vec<u8>>> {
+        let tree = self.tree()?;
+        cas::fetch_and_update(py, &tree, &key, f)
+    }
+
+    /// Deprecated alias of `compare_and_swap` that returns the error instead of raising it.
     pub fn compare_and_swamp(
         &self,
-        key: &[u8],
-        old: Option<&[u8]>,
-        new: Option<Vec<u8>>,
-    ) -> PyResult<Option<CompareAndSwapError>> {
-        convert_to_pyresult(self.inner.compare_and_swap(key, old, new)).map(|e| {
-            e.map_err(|i| CompareAndSwapError {
-                current: i.current.map(|e| e.to_vec()),
-                proposed: i.proposed.map(|e| e.to_vec()),
-            })
-            .err()
-        })
+        py: Python,
+        key: Bytes,
+        old: Option<Bytes>,
+        new: Option<Bytes>,
+    ) -> PyResult<Option<PyObject>> {
+        let tree = self.tree()?;
+        cas::compare_and_swamp(py, &tree, &key, old.as_deref(), new.as_deref())
+    }
+
+    pub fn checksum(&self, py: Python) -> PyResult<u32> {
+        let tree = self.tree()?;
+        convert_to_pyresult(py.allow_threads(|| tree.checksum()))
+    }
+
+    pub fn flush(&self, py: Python) -> PyResult<usize> {
+        let tree = self.tree()?;
+        convert_to_pyresult(py.allow_threads(|| tree.flush()))
     }
 
-    pub fn checksum(&self) -> PyResult<u32> {
-        convert_to_pyresult(self.inner.checksum())
+    pub fn flush_async<'p>(&self, py: Python<'p>) -> PyResult<&'p PyAny> {
+        let tree = self.tree()?;
+        asyncio::future_into_py(
+            py,
+            async move { tree.flush_async().await },
+            |py, r| convert_to_pyresult(r).map(|n| n.into_py(py)),
+            drop,
+        )
     }
 
-    pub fn flush(&self) -> PyResult<usize> {
-        convert_to_pyresult(self.inner.flush())
+    pub fn is_empty(&self, py: Python) -> PyResult<bool> {
+        let tree = self.tree()?;
+        Ok(py.allow_threads(|| tree.is_empty()))
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.inner.is_empty()
+    pub fn __len__(&self, py: Python) -> PyResult<usize> {
+        let tree = self.tree()?;
+        Ok(py.allow_threads(|| tree.len()))
     }
 
-    pub fn __len__(&self) -> usize {
-        self.inner.len()
+    pub fn __contains__(&self, py: Python, key: Bytes) -> PyResult<bool> {
+        let tree = self.tree()?;
+        convert_to_pyresult(py.allow_threads(|| tree.contains_key(key)))
     }
 
-    pub fn __contains__(&self, key: &[u8]) -> PyResult<bool> {
-        convert_to_pyresult(self.inner.contains_key(key))
+    pub fn __getitem__(&self, py: Python, key: Bytes) -> PyResult<Vec<u8>> {
+        let tree = self.tree()?;
+        mapping::get_item(py, &tree, &key)
     }
 
-    pub fn __getitem__(&self, key: &[u8]) -> PyResult<Option<Vec<u8>>> {
-        self.get(key)
+    pub fn __setitem__(&self, py: Python, key: Bytes, value: Bytes) -> PyResult<()> {
+        self.insert(py, key, value).map(|_| ())
     }
 
-    pub fn __setitem__(&self, key: &[u8], value: Vec<u8>) -> PyResult<()> {
-        self.insert(key, value).map(|_| ())
+    pub fn __delitem__(&self, py: Python, key: Bytes) -> PyResult<()> {
+        let tree = self.tree()?;
+        mapping::del_item(py, &tree, &key)
     }
 
-    pub fn __delitem__(&self, key: &[u8]) -> PyResult<()> {
-        self.remove(key).map(|_| ())
+    /// Iterates over the keys, like `keys()`.
+    pub fn __iter__(&self) -> PyResult<SledIter> {
+        let tree = self.tree()?;
+        Ok(iter::view(&self.registry, &tree, IterKind::Keys))
+    }
+
+    /// Removes `key` and returns its value, or `default` if it is missing.
+    #[args(default = "*")]
+    pub fn pop(&self, py: Python, key: Bytes, default: &PyTuple) -> PyResult<PyObject> {
+        let tree = self.tree()?;
+        mapping::pop(py, &tree, &key, default)
+    }
+
+    /// Removes and returns the `(key, value)` pair with the smallest key.
+    pub fn popitem(&self, py: Python) -> PyResult<Entry> {
+        let tree = self.tree()?;
+        mapping::popitem(py, &tree)
+    }
+
+    /// Atomically inserts `default` unless `key` is present, and returns the stored value.
+    pub fn setdefault(&self, py: Python, key: Bytes, default: Bytes) -> PyResult<Vec<u8>> {
+        let tree = self.tree()?;
+        mapping::setdefault(py, &tree, &key, &default)
+    }
+
+    /// Inserts the entries of a mapping or an iterable of `(key, value)` pairs as one batch.
+    pub fn update(&self, py: Python, other: &PyAny) -> PyResult<()> {
+        let tree = self.tree()?;
+        mapping::update(py, &tree, other)
+    }
+
+    /// Equal to any mapping with the same keys whose values hold the same bytes, or equal the
+    /// values as `get` returns them.
+    pub fn __richcmp__(&self, py: Python, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        let tree = self.tree()?;
+        mapping::richcmp(py, op, || mapping::equals(py, &tree, other))
     }
 
     #[getter]
-    pub fn name(&self) -> Vec<u8> {
-        self.inner.name().to_vec()
+    pub fn name<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        let tree = self.tree()?;
+        Ok(PyBytes::new(py, &tree.name()))
     }
-}
 
-/// Formats the sum of two numbers as string.
-#[pyfunction]
-fn sum_as_string(a: usize, b: usize) -> PyResult<String> {
-    Ok((a + b).to_string())
+    pub fn contains_key(&self, py: Python, key: Bytes) -> PyResult<bool> {
+        let tree = self.tree()?;
+        convert_to_pyresult(py.allow_threads(|| tree.contains_key(key)))
+    }
+
+    pub fn len(&self, py: Python) -> PyResult<usize> {
+        let tree = self.tree()?;
+        Ok(py.allow_threads(|| tree.len()))
+    }
 }
 
 /// A Python module implemented in Rust.
 #[pymodule]
-fn pysled(_py: Python, m: &PyModule) -> PyResult<()> {
+#[pyo3(name = "_pysled")]
+fn pysled(py: Python, m: &PyModule) -> PyResult<()> {
+    m.add("__version__", info::VERSION)?;
+    m.add("sled_version", info::SLED_VERSION)?;
     m.add_class::<SledDb>()?;
     m.add_class::<SledTree>()?;
-    m.add_function(wrap_pyfunction!(sum_as_string, m)?)?;
+    m.add_class::<SledConfig>()?;
+    m.add_class::<SledIter>()?;
+    m.add_class::<SledTransactionalTree>()?;
+    m.add_class::<SledBatch>()?;
+    m.add_class::<SledSubscriber>()?;
+    m.add_class::<InsertEvent>()?;
+    m.add_class::<RemoveEvent>()?;
+    m.add_class::<SledValue>()?;
+    m.add_class::<SledTypedTree>()?;
+    m.add_class::<PickleCodec>()?;
+    m.add_class::<SledKeysView>()?;
+    m.add_class::<SledValuesView>()?;
+    m.add_class::<SledItemsView>()?;
+    m.add("SledError", py.get_type::<SledError>())?;
+    m.add("CollectionNotFound", py.get_type::<CollectionNotFound>())?;
+    m.add("Unsupported", py.get_type::<Unsupported>())?;
+    m.add("ReportableBug", py.get_type::<ReportableBug>())?;
+    m.add("IoError", error::io_error(py))?;
+    m.add("Corruption", py.get_type::<Corruption>())?;
+    m.add("SledClosedError", py.get_type::<SledClosedError>())?;
+    m.add("CodecError", py.get_type::<CodecError>())?;
+    m.add("CompareAndSwapError", py.get_type::<CompareAndSwapError>())?;
+    let mutable_mapping = py.import("collections.abc")?.getattr("MutableMapping")?;
+    // `SledDb` is covered as a subclass of `SledTree`
+    for class in ["SledTree", "SledTypedTree"] {
+        mutable_mapping.call_method1("register", (m.getattr(class)?,))?;
+    }
+    for (view, class) in [
+        ("KeysView", "SledKeysView"),
+        ("ValuesView", "SledValuesView"),
+        ("ItemsView", "SledItemsView"),
+    ] {
+        let abc = py.import("collections.abc")?.getattr(view)?;
+        abc.call_method1("register", (m.getattr(class)?,))?;
+    }
+    m.add_function(wrap_pyfunction!(transaction::transaction, m)?)?;
+    m.add_function(wrap_pyfunction!(bytes::set_strict_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(bytes::strict_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(info::build_info, m)?)?;
+    let keys = keys::module(py)?;
+    m.add_submodule(keys)?;
+    // lets `import pysled.keys` find the submodule, which has no file in the package
+    py.import("sys")?
+        .getattr("modules")?
+        .set_item("pysled.keys", keys)?;
+    py.import("atexit")?
+        .call_method1("register", (wrap_pyfunction!(asyncio::shutdown, m)?,))?;
     Ok(())
 }
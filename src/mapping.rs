@@ -0,0 +1,152 @@
+use pyo3::{
+    basic::CompareOp,
+    exceptions::{PyKeyError, PyTypeError},
+    prelude::*,
+    types::{PyBytes, PyTuple, PyType},
+};
+use sled::{Batch, Tree};
+
+use crate::{
+    bytes::Bytes,
+    convert_to_pyresult,
+    ordered::{self, Entry},
+    SledTree,
+};
+
+pub(crate) fn key_error(py: Python, key: &[u8]) -> PyErr {
+    PyKeyError::new_err(Py::<PyBytes>::from(PyBytes::new(py, key)))
+}
+
+pub(crate) fn is_mapping(other: &PyAny) -> PyResult<bool> {
+    let mapping = other.py().import("collections.abc")?.getattr("Mapping")?;
+    other.is_instance(mapping.downcast::<PyType>()?)
+}
+
+/// Implements `==` and `!=` with `eq`, which returns `None` if it cannot compare to `other`.
+pub(crate) fn richcmp(
+    py: Python,
+    op: CompareOp,
+    eq: impl FnOnce() -> PyResult<Option<bool>>,
+) -> PyResult<PyObject> {
+    let eq = match op {
+        CompareOp::Eq => eq()?,
+        CompareOp::Ne => eq()?.map(|eq| !eq),
+        _ => None,
+    };
+    Ok(eq.map_or_else(|| py.NotImplemented(), |eq| eq.into_py(py)))
+}
+
+/// The optional default of `pop(key[, default])`, or the error raised when the key is missing.
+pub(crate) fn pop_default(
+    default: &PyTuple,
+    missing: impl FnOnce() -> PyErr,
+) -> PyResult<PyObject> {
+    match default.len() {
+        0 => Err(missing()),
+        1 => Ok(default.get_item(0)?.into()),
+        n => Err(PyTypeError::new_err(format!(
+            "pop expected at most 2 arguments, got {}",
+            n + 1
+        ))),
+    }
+}
+
+/// The `(key, value)` pairs of a mapping, or of an iterable of pairs, like `dict.update` takes.
+pub(crate) fn pairs(other: &PyAny) -> PyResult<Vec<(&PyAny, &PyAny)>> {
+    if other.hasattr("keys")? {
+        other
+            .call_method0("keys")?
+            .iter()?
+            .map(|key| {
+                let key = key?;
+                Ok((key, other.get_item(key)?))
+            })
+            .collect()
+    } else {
+        other.iter()?.map(|pair| pair?.extract()).collect()
+    }
+}
+
+pub(crate) fn get_item(py: Python, tree: &Tree, key: &[u8]) -> PyResult<Vec<u8>> {
+    convert_to_pyresult(py.allow_threads(|| tree.get(key)))?
+        .map(|v| v.to_vec())
+        .ok_or_else(|| key_error(py, key))
+}
+
+pub(crate) fn del_item(py: Python, tree: &Tree, key: &[u8]) -> PyResult<()> {
+    convert_to_pyresult(py.allow_threads(|| tree.remove(key)))?
+        .map(drop)
+        .ok_or_else(|| key_error(py, key))
+}
+
+pub(crate) fn pop(py: Python, tree: &Tree, key: &[u8], default: &PyTuple) -> PyResult<PyObject> {
+    match convert_to_pyresult(py.allow_threads(|| tree.remove(key)))? {
+        Some(v) => Ok(v.to_vec().into_py(py)),
+        None => pop_default(default, || key_error(py, key)),
+    }
+}
+
+/// Removes and returns the entry with the smallest key.
+pub(crate) fn popitem(py: Python, tree: &Tree) -> PyResult<Entry> {
+    ordered::pop_min(py, tree)?.ok_or_else(|| PyKeyError::new_err("popitem(): the tree is empty"))
+}
+
+/// Inserts `default` unless `key` is present, atomically, and returns the stored value.
+pub(crate) fn setdefault(py: Python, tree: &Tree, key: &[u8], default: &[u8]) -> PyResult<Vec<u8>> {
+    let swapped =
+        py.allow_threads(|| tree.compare_and_swap(key, None as Option<&[u8]>, Some(default)));
+    match convert_to_pyresult(swapped)? {
+        Ok(()) => Ok(default.to_vec()),
+        Err(e) => Ok(e.current.map_or_else(|| default.to_vec(), |v| v.to_vec())),
+    }
+}
+
+/// Inserts everything in `other` as one atomic batch. Another tree is copied as stored, without
+/// converting its entries to Python objects.
+pub(crate) fn update(py: Python, tree: &Tree, other: &PyAny) -> PyResult<()> {
+    if let Ok(other) = other.extract::<PyRef<SledTree>>() {
+        let other = other.tree()?;
+        return convert_to_pyresult(py.allow_threads(|| {
+            let mut batch = Batch::default();
+            for entry in other.iter() {
+                let (key, value) = entry?;
+                batch.insert(key, value);
+            }
+            tree.apply_batch(batch)
+        }));
+    }
+    let mut batch = Batch::default();
+    for (key, value) in pairs(other)? {
+        batch.insert(key.extract::<Bytes>()?, value.extract::<Bytes>()?);
+    }
+    convert_to_pyresult(py.allow_threads(|| tree.apply_batch(batch)))
+}
+
+/// Whether `other` is a mapping with the same keys, whose values hold the same bytes or are equal
+/// to the values as `get` returns them.
+pub(crate) fn equals(py: Python, tree: &Tree, other: &PyAny) -> PyResult<Option<bool>> {
+    if !is_mapping(other)? {
+        return Ok(None);
+    }
+    if other.len()? != py.allow_threads(|| tree.len()) {
+        return Ok(Some(false));
+    }
+    // holding the GIL while waiting for sled's lock would deadlock with a running transaction
+    let mut entries = tree.iter();
+    while let Some(entry) = py.allow_threads(|| entries.next()) {
+        let (k, v) = convert_to_pyresult(entry)?;
+        let theirs = match other.get_item(PyBytes::new(py, &k)) {
+            Ok(theirs) => theirs,
+            Err(e) if e.is_instance_of::<PyKeyError>(py) => return Ok(Some(false)),
+            Err(e) => return Err(e),
+        };
+        let equal = match theirs.extract::<Bytes>() {
+            Ok(theirs) => *theirs == *v,
+            Err(_) => theirs.eq(v.to_vec())?,
+        };
+        if !equal {
+            return Ok(Some(false));
+        }
+    }
+    Ok(Some(true))
+}
@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+
+use pyo3::{
+    exceptions::{PyTypeError, PyValueError},
+    ffi,
+    prelude::*,
+    types::PyString,
+    AsPyPointer,
+};
+use sled::{IVec, Tree};
+
+use crate::{bytes::Bytes, convert_to_pyresult};
+
+#[derive(Clone, Copy)]
+enum BuiltinMerge {
+    U64Add,
+    Append,
+    SetUnion,
+    Max,
+    Min,
+}
+
+impl BuiltinMerge {
+    fn parse(name: &str) -> PyResult<Self> {
+        match name {
+            "u64_add" => Ok(Self::U64Add),
+            "append" => Ok(Self::Append),
+            "set_union" => Ok(Self::SetUnion),
+            "max" => Ok(Self::Max),
+            "min" => Ok(Self::Min),
+            other => Err(PyValueError::new_err(format!(
+                "Unknown merge operator {:?}, expected one of \"u64_add\", \"append\", \"set_union\", \"max\" or \"min\"",
+                other
+            ))),
+        }
+    }
+
+    fn merge(self, old: Option<&[u8]>, new: &[u8]) -> Option<Vec<u8>> {
+        match self {
+            Self::U64Add => u64_add(old, new),
+            Self::Append => {
+                let mut out = old.map(<[u8]>::to_vec).unwrap_or_default();
+                out.extend_from_slice(new);
+                Some(out)
+            }
+            Self::SetUnion => set_union(old, new),
+            Self::Max => Some(old.map_or(new, |old| old.max(new)).to_vec()),
+            Self::Min => Some(old.map_or(new, |old| old.min(new)).to_vec()),
+        }
+    }
+}
+
+fn u64_add(old: Option<&[u8]>, new: &[u8]) -> Option<Vec<u8>> {
+    let unchanged = || old.map(<[u8]>::to_vec);
+    let new = match <[u8; 8]>::try_from(new) {
+        Ok(new) => u64::from_be_bytes(new),
+        Err(_) => return unchanged(),
+    };
+    let old = match old.map(<[u8; 8]>::try_from) {
+        Some(Ok(old)) => u64::from_be_bytes(old),
+        Some(Err(_)) => return unchanged(),
+        None => 0,
+    };
+    Some(old.wrapping_add(new).to_be_bytes().to_vec())
+}
+
+/// Splits a buffer of items that are each prefixed by their length as a big-endian u32.
+fn split_items(mut buf: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut items = Vec::new();
+    while !buf.is_empty() {
+        let len = u32::from_be_bytes(buf.get(..4)?.try_into().ok()?) as usize;
+        items.push(buf.get(4..4 + len)?);
+        buf = &buf[4 + len..];
+    }
+    Some(items)
+}
+
+fn set_union(old: Option<&[u8]>, new: &[u8]) -> Option<Vec<u8>> {
+    let unchanged = || old.map(<[u8]>::to_vec);
+    let mut items = match old.map(split_items) {
+        Some(Some(items)) => items.into_iter().collect::<BTreeSet<_>>(),
+        Some(None) => return unchanged(),
+        None => BTreeSet::new(),
+    };
+    match split_items(new) {
+        Some(new) => items.extend(new),
+        None => return unchanged(),
+    }
+    let mut out = Vec::new();
+    for item in items {
+        out.extend_from_slice(&(item.len() as u32).to_be_bytes());
+        out.extend_from_slice(item);
+    }
+    Some(out)
+}
+
+fn call_python(f: &PyObject, key: &[u8], old: Option<&[u8]>, new: &[u8]) -> Option<Vec<u8>> {
+    Python::with_gil(|py| {
+        match f
+            .call1(py, (key, old, new))
+            .and_then(|ret| Ok(ret.extract::<Option<Bytes>>(py)?.map(|b| b.to_vec())))
+        {
+            Ok(ret) => ret,
+            Err(e) => {
+                // there is no caller to raise into, so report it like an exception in __del__
+                // and leave the stored value untouched
+                e.restore(py);
+                unsafe { ffi::PyErr_WriteUnraisable(f.as_ptr()) };
+                old.map(<[u8]>::to_vec)
+            }
+        }
+    })
+}
+
+/// Installs either a Python callable `(key, old, new) -> Optional[bytes]` or one of the named
+/// native operators as the merge operator of `tree`.
+///
+/// The native operators never touch the interpreter:
+/// - "u64_add": adds 8 byte big-endian unsigned integers, wrapping on overflow
+/// - "append": concatenates the new bytes to the old value
+/// - "set_union": merges sets of items that are each prefixed by a big-endian u32 length
+/// - "max" / "min": keeps the lexicographically larger / smaller value
+///
+/// Operands the numeric and set operators cannot decode leave the stored value unchanged.
+pub(crate) fn set_merge_operator(py: Python, tree: &Tree, operator: &PyAny) -> PyResult<()> {
+    if let Ok(name) = operator.downcast::<PyString>() {
+        let builtin = BuiltinMerge::parse(name.to_str()?)?;
+        py.allow_threads(|| {
+            tree.set_merge_operator(move |_key: &[u8], old: Option<&[u8]>, new: &[u8]| {
+                builtin.merge(old, new)
+            })
+        });
+    } else if operator.is_callable() {
+        let f: PyObject = operator.into();
+        py.allow_threads(|| {
+            tree.set_merge_operator(move |key: &[u8], old: Option<&[u8]>, new: &[u8]| {
+                call_python(&f, key, old, new)
+            })
+        });
+    } else {
+        return Err(PyTypeError::new_err(
+            "merge operator must be a callable or the name of a builtin operator",
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn merge(
+    py: Python,
+    tree: &Tree,
+    key: &[u8],
+    value: &[u8],
+) -> PyResult<Option<Vec<u8>>> {
+    convert_to_pyresult(py.allow_threads(|| tree.merge(key, value)))
+        .map(|o| o.map(|i: IVec| i.to_vec()))
+}
@@ -0,0 +1,78 @@
+use pyo3::{prelude::*, types::PyBytes};
+use sled::{
+    transaction::{abort, TransactionError},
+    IVec, Tree,
+};
+
+use crate::convert_to_pyresult;
+
+/// A `(key, value)` pair, with the key as `bytes` like iterators yield it.
+pub(crate) type Entry = (Py<PyBytes>, Vec<u8>);
+
+pub(crate) fn entry(py: Python, (k, v): (IVec, IVec)) -> Entry {
+    (PyBytes::new(py, &k).into(), v.to_vec())
+}
+
+fn to_entry(py: Python, e: sled::Result<Option<(IVec, IVec)>>) -> PyResult<Option<Entry>> {
+    convert_to_pyresult(e).map(|e| e.map(|e| entry(py, e)))
+}
+
+pub(crate) fn first(py: Python, tree: &Tree) -> PyResult<Option<Entry>> {
+    to_entry(py, py.allow_threads(|| tree.first()))
+}
+
+pub(crate) fn last(py: Python, tree: &Tree) -> PyResult<Option<Entry>> {
+    to_entry(py, py.allow_threads(|| tree.last()))
+}
+
+pub(crate) fn get_lt(py: Python, tree: &Tree, key: &[u8]) -> PyResult<Option<Entry>> {
+    to_entry(py, py.allow_threads(|| tree.get_lt(key)))
+}
+
+pub(crate) fn get_gt(py: Python, tree: &Tree, key: &[u8]) -> PyResult<Option<Entry>> {
+    to_entry(py, py.allow_threads(|| tree.get_gt(key)))
+}
+
+pub(crate) fn floor(py: Python, tree: &Tree, key: &[u8]) -> PyResult<Option<Entry>> {
+    to_entry(
+        py,
+        py.allow_threads(|| tree.range(..=key).next_back().transpose()),
+    )
+}
+
+pub(crate) fn ceiling(py: Python, tree: &Tree, key: &[u8]) -> PyResult<Option<Entry>> {
+    to_entry(
+        py,
+        py.allow_threads(|| tree.range(key..).next().transpose()),
+    )
+}
+
+pub(crate) fn pop_min(py: Python, tree: &Tree) -> PyResult<Option<Entry>> {
+    to_entry(py, py.allow_threads(|| tree.pop_min()))
+}
+
+pub(crate) fn pop_max(py: Python, tree: &Tree) -> PyResult<Option<Entry>> {
+    to_entry(py, py.allow_threads(|| tree.pop_max()))
+}
+
+pub(crate) fn pop_min_n(py: Python, tree: &Tree, n: usize) -> PyResult<Vec<Entry>> {
+    let popped = py.allow_threads(|| loop {
+        let entries = tree.iter().take(n).collect::<sled::Result<Vec<_>>>()?;
+        // removed in one transaction, so a failure cannot lose entries that were already taken
+        let removed = tree.transaction(|tx| {
+            for (k, v) in &entries {
+                if tx.remove(k)?.as_ref() != Some(v) {
+                    return abort(());
+                }
+            }
+            Ok(())
+        });
+        match removed {
+            Ok(()) => return Ok(entries),
+            // another writer changed one of the entries in between
+            Err(TransactionError::Abort(())) => continue,
+            Err(TransactionError::Storage(e)) => return Err(e),
+        }
+    });
+    convert_to_pyresult(popped).map(|popped| popped.into_iter().map(|e| entry(py, e)).collect())
+}
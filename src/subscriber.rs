@@ -0,0 +1,211 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{mpsc::RecvTimeoutError, Arc, Mutex, MutexGuard, TryLockError},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use pyo3::{
+    exceptions::{PyStopAsyncIteration, PyTimeoutError, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+use sled::{Event, Subscriber, Tree};
+
+use crate::asyncio;
+
+// blocking reads wake up this often to give Ctrl-C a chance to interrupt them
+const SIGNAL_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+#[pyclass]
+pub struct InsertEvent {
+    #[pyo3(get)]
+    pub key: Py<PyBytes>,
+    #[pyo3(get)]
+    pub value: Vec<u8>,
+}
+
+#[pymethods]
+impl InsertEvent {
+    pub fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!(
+            "InsertEvent(key={}, value={:?})",
+            self.key.as_ref(py).repr()?,
+            self.value
+        ))
+    }
+}
+
+#[pyclass]
+pub struct RemoveEvent {
+    #[pyo3(get)]
+    pub key: Py<PyBytes>,
+}
+
+#[pymethods]
+impl RemoveEvent {
+    pub fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!("RemoveEvent(key={})", self.key.as_ref(py).repr()?))
+    }
+}
+
+pub(crate) fn event_into_py(py: Python, event: Event) -> PyResult<PyObject> {
+    Ok(match event {
+        Event::Insert { key, value } => Py::new(
+            py,
+            InsertEvent {
+                key: PyBytes::new(py, &key).into(),
+                value: value.to_vec(),
+            },
+        )?
+        .into_py(py),
+        Event::Remove { key } => Py::new(
+            py,
+            RemoveEvent {
+                key: PyBytes::new(py, &key).into(),
+            },
+        )?
+        .into_py(py),
+    })
+}
+
+// The subscriber is locked for the whole wait of a blocking read, while the queue is only ever
+// locked briefly, so async reads can check it without stalling the runtime.
+struct Shared {
+    subscriber: Mutex<Subscriber>,
+    queue: Mutex<Queue>,
+}
+
+struct Queue {
+    // an event whose async read was cancelled after it had already been received
+    pending: Option<Event>,
+    // async reads waiting for an event to be put back or for a blocking read to release the
+    // subscriber
+    wakers: Vec<Waker>,
+}
+
+impl Shared {
+    fn next_timeout(&self, timeout: Duration) -> Result<Event, RecvTimeoutError> {
+        if let Some(event) = lock(&self.queue).pending.take() {
+            return Ok(event);
+        }
+        let next = lock(&self.subscriber).next_timeout(timeout);
+        self.wake_waiting();
+        next
+    }
+
+    fn poll_next(&self, cx: &mut Context) -> Poll<Option<Event>> {
+        let mut queue = lock(&self.queue);
+        if let Some(event) = queue.pending.take() {
+            return Poll::Ready(Some(event));
+        }
+        // a blocking read may hold the subscriber for its whole timeout, which would stall every
+        // other future on the runtime
+        let poll = match self.subscriber.try_lock() {
+            Ok(mut subscriber) => Pin::new(&mut *subscriber).poll(cx),
+            Err(TryLockError::Poisoned(e)) => Pin::new(&mut *e.into_inner()).poll(cx),
+            Err(TryLockError::WouldBlock) => Poll::Pending,
+        };
+        if poll.is_pending() && !queue.wakers.iter().any(|w| w.will_wake(cx.waker())) {
+            queue.wakers.push(cx.waker().clone());
+        }
+        poll
+    }
+
+    fn put_back(&self, event: Option<Event>) {
+        lock(&self.queue).pending = event;
+        self.wake_waiting();
+    }
+
+    fn wake_waiting(&self) {
+        let wakers = std::mem::take(&mut lock(&self.queue).wakers);
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+}
+
+/// Yields an `InsertEvent` or `RemoveEvent` for every change to keys under the watched prefix.
+/// Iteration blocks until the next event arrives and stops once the database is closed.
+/// `async for` waits for events without blocking the event loop.
+#[pyclass]
+pub struct SledSubscriber {
+    // shared with the futures handed out by `__anext__`
+    inner: Arc<Shared>,
+}
+
+#[pymethods]
+impl SledSubscriber {
+    pub fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __next__(&self, py: Python) -> PyResult<Option<PyObject>> {
+        loop {
+            let inner = &self.inner;
+            match py.allow_threads(|| inner.next_timeout(SIGNAL_CHECK_INTERVAL)) {
+                Ok(event) => return event_into_py(py, event).map(Some),
+                Err(RecvTimeoutError::Timeout) => py.check_signals()?,
+                Err(RecvTimeoutError::Disconnected) => return Ok(None),
+            }
+        }
+    }
+
+    /// Waits for the next event. Without a timeout this behaves like `next(subscriber)`, otherwise
+    /// `TimeoutError` is raised if nothing arrives within `timeout` seconds. Returns `None` once
+    /// the database is closed.
+    #[args(timeout = "None")]
+    pub fn next(&self, py: Python, timeout: Option<f64>) -> PyResult<Option<PyObject>> {
+        let timeout = match timeout {
+            Some(timeout) => Duration::try_from_secs_f64(timeout)
+                .map_err(|e| PyValueError::new_err(e.to_string()))?,
+            None => return self.__next__(py),
+        };
+        let inner = &self.inner;
+        match py.allow_threads(|| inner.next_timeout(timeout)) {
+            Ok(event) => event_into_py(py, event).map(Some),
+            Err(RecvTimeoutError::Timeout) => Err(PyTimeoutError::new_err(
+                "No event arrived within the timeout",
+            )),
+            Err(RecvTimeoutError::Disconnected) => Ok(None),
+        }
+    }
+
+    pub fn __aiter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    pub fn __anext__<'p>(&self, py: Python<'p>) -> PyResult<Option<&'p PyAny>> {
+        let inner = self.inner.clone();
+        let next = std::future::poll_fn(move |cx| inner.poll_next(cx));
+        let inner = self.inner.clone();
+        asyncio::future_into_py(
+            py,
+            next,
+            |py, event| match event {
+                Some(event) => event_into_py(py, event),
+                None => Err(PyStopAsyncIteration::new_err(())),
+            },
+            move |event| inner.put_back(event),
+        )
+        .map(Some)
+    }
+}
+
+fn lock<T>(inner: &Mutex<T>) -> MutexGuard<'_, T> {
+    // a panic while holding the lock cannot leave the subscriber in an inconsistent state
+    inner.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn watch_prefix(tree: &Tree, prefix: &[u8]) -> SledSubscriber {
+    SledSubscriber {
+        inner: Arc::new(Shared {
+            subscriber: Mutex::new(tree.watch_prefix(prefix)),
+            queue: Mutex::new(Queue {
+                pending: None,
+                wakers: vec![],
+            }),
+        }),
+    }
+}
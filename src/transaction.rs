@@ -0,0 +1,140 @@
+use std::{cell::RefCell, rc::Rc};
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+    types::PyTuple,
+};
+use sled::{
+    transaction::{
+        ConflictableTransactionError, ConflictableTransactionResult, TransactionError,
+        TransactionalTree, UnabortableTransactionError,
+    },
+    Transactional, Tree,
+};
+
+use crate::{
+    bytes::Bytes,
+    error::{self, SledError},
+    SledTree,
+};
+
+type SharedError = Rc<RefCell<Option<UnabortableTransactionError>>>;
+
+/// A view of a tree inside a running transaction. Only valid while the transaction callback runs.
+#[pyclass(unsendable, name = "TransactionalTree")]
+pub struct SledTransactionalTree {
+    inner: Option<TransactionalTree>,
+    // Conflicts and storage errors have to reach sled even if the callback swallows the exception.
+    error: SharedError,
+}
+
+impl SledTransactionalTree {
+    fn convert<T>(&self, inp: Result<T, UnabortableTransactionError>) -> PyResult<T> {
+        inp.map_err(|e| {
+            let err = match &e {
+                UnabortableTransactionError::Storage(e) => {
+                    Python::with_gil(|py| error::to_pyerr(py, e.clone()))
+                }
+                UnabortableTransactionError::Conflict => SledError::new_err(e.to_string()),
+            };
+            self.error.borrow_mut().get_or_insert(e);
+            err
+        })
+    }
+
+    fn tree(&self) -> PyResult<&TransactionalTree> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| PyRuntimeError::new_err("Transaction is no longer active"))
+    }
+}
+
+#[pymethods]
+impl SledTransactionalTree {
+    pub fn insert(&self, key: Bytes, value: Bytes) -> PyResult<Option<Vec<u8>>> {
+        self.convert(self.tree()?.insert(key, value))
+            .map(|o| o.map(|i| i.to_vec()))
+    }
+
+    pub fn get(&self, key: Bytes) -> PyResult<Option<Vec<u8>>> {
+        self.convert(self.tree()?.get(key))
+            .map(|o| o.map(|i| i.to_vec()))
+    }
+
+    pub fn remove(&self, key: Bytes) -> PyResult<Option<Vec<u8>>> {
+        self.convert(self.tree()?.remove(key))
+            .map(|o| o.map(|i| i.to_vec()))
+    }
+}
+
+/// Runs `f` inside a transaction over `trees`, passing one `TransactionalTree` per tree as
+/// positional arguments. The callback is retried on conflict, so it should not have side effects
+/// outside of the transaction. An exception raised by `f` aborts the transaction and is re-raised.
+pub(crate) fn run(py: Python, trees: &[Tree], f: &PyAny) -> PyResult<PyObject> {
+    let f: PyObject = f.into();
+    // sled takes its exclusive lock before calling into the closure, which must not happen while
+    // holding the GIL, since merge operators may be waiting for it while holding a shared lock
+    let result = py.allow_threads(|| {
+        trees.transaction(|views: &Vec<TransactionalTree>| {
+            Python::with_gil(|py| call_transaction(py, views, &f))
+        })
+    });
+    result.map_err(|e| match e {
+        TransactionError::Abort(e) => e,
+        TransactionError::Storage(e) => error::to_pyerr(py, e),
+    })
+}
+
+fn call_transaction(
+    py: Python,
+    views: &[TransactionalTree],
+    f: &PyObject,
+) -> ConflictableTransactionResult<PyObject, PyErr> {
+    let error = SharedError::default();
+    let wrappers = views
+        .iter()
+        .map(|view| {
+            Py::new(
+                py,
+                SledTransactionalTree {
+                    inner: Some(view.clone()),
+                    error: error.clone(),
+                },
+            )
+        })
+        .collect::<PyResult<Vec<_>>>()
+        .map_err(ConflictableTransactionError::Abort)?;
+    let ret = f.call1(py, PyTuple::new(py, &wrappers));
+    for wrapper in &wrappers {
+        wrapper.borrow_mut(py).inner = None;
+    }
+    let error = error.borrow_mut().take();
+    match (error, ret) {
+        (Some(UnabortableTransactionError::Conflict), _) => {
+            Err(ConflictableTransactionError::Conflict)
+        }
+        (Some(UnabortableTransactionError::Storage(e)), _) => {
+            Err(ConflictableTransactionError::Storage(e))
+        }
+        (None, Ok(ret)) => Ok(ret),
+        (None, Err(e)) => Err(ConflictableTransactionError::Abort(e)),
+    }
+}
+
+/// Runs `f` in a single transaction spanning all of `trees`, which must belong to the same
+/// database. `SledDb` objects can be passed to include the default tree.
+#[pyfunction]
+pub fn transaction(py: Python, trees: Vec<&PyAny>, f: &PyAny) -> PyResult<PyObject> {
+    // sled indexes the first tree to commit
+    if trees.is_empty() {
+        return Err(PyValueError::new_err(
+            "transaction() needs at least one tree",
+        ));
+    }
+    let trees = trees
+        .into_iter()
+        .map(|t| t.extract::<PyRef<SledTree>>()?.tree())
+        .collect::<PyResult<Vec<Tree>>>()?;
+    run(py, &trees, f)
+}
@@ -0,0 +1,249 @@
+use std::{ops::Bound, sync::Arc};
+
+use pyo3::{
+    basic::CompareOp,
+    exceptions::PyKeyError,
+    prelude::*,
+    types::{PyBytes, PyTuple},
+};
+use sled::{Batch, CompareAndSwapError, IVec, Iter};
+
+use crate::{
+    codec::Codecs,
+    convert_to_pyresult,
+    iter::{IterKind, SledIter},
+    mapping,
+    view::{SledItemsView, SledKeysView, SledValuesView, ViewSource},
+    SledTree,
+};
+
+/// A view of a tree that encodes keys and values with codecs, returned by `open_tree` when a
+/// `key_codec` or `value_codec` is given. Iterators yield decoded objects, in the order of the
+/// encoded keys.
+#[pyclass(mapping)]
+pub struct SledTypedTree {
+    raw: SledTree,
+    codecs: Arc<Codecs>,
+}
+
+impl SledTypedTree {
+    pub(crate) fn new(raw: SledTree, codecs: Codecs) -> Self {
+        Self {
+            raw,
+            codecs: Arc::new(codecs),
+        }
+    }
+
+    fn decode_value(&self, py: Python, value: Option<IVec>) -> PyResult<Option<PyObject>> {
+        value.map(|v| self.codecs.decode_value(py, &v)).transpose()
+    }
+
+    fn view_source(slf: PyRef<Self>, py: Python) -> ViewSource {
+        let (raw, codecs) = (slf.raw.clone(), slf.codecs.clone());
+        ViewSource::new(slf.into_py(py), raw, Some(codecs))
+    }
+
+    fn iter(&self, iter: Iter, kind: IterKind) -> SledIter {
+        SledIter::new(&self.raw.registry, iter, kind).with_codecs(self.codecs.clone())
+    }
+
+    fn equals(&self, py: Python, other: &PyAny) -> PyResult<Option<bool>> {
+        if !mapping::is_mapping(other)? {
+            return Ok(None);
+        }
+        let tree = self.raw.tree()?;
+        if other.len()? != py.allow_threads(|| tree.len()) {
+            return Ok(Some(false));
+        }
+        let mut entries = tree.iter();
+        while let Some(entry) = py.allow_threads(|| entries.next()) {
+            let (k, v) = convert_to_pyresult(entry)?;
+            let theirs = match other.get_item(self.codecs.decode_key(py, &k)?) {
+                Ok(theirs) => theirs,
+                Err(e) if e.is_instance_of::<PyKeyError>(py) => return Ok(Some(false)),
+                Err(e) => return Err(e),
+            };
+            if !theirs.eq(self.codecs.decode_value(py, &v)?)? {
+                return Ok(Some(false));
+            }
+        }
+        Ok(Some(true))
+    }
+}
+
+#[pymethods]
+impl SledTypedTree {
+    pub fn insert(&self, py: Python, key: &PyAny, value: &PyAny) -> PyResult<Option<PyObject>> {
+        let tree = self.raw.tree()?;
+        let key = self.codecs.encode_key(key)?;
+        let value = self.codecs.encode_value(value)?;
+        let old = convert_to_pyresult(py.allow_threads(|| tree.insert(key, value)))?;
+        self.decode_value(py, old)
+    }
+
+    #[args(default = "None")]
+    pub fn get(
+        &self,
+        py: Python,
+        key: &PyAny,
+        default: Option<PyObject>,
+    ) -> PyResult<Option<PyObject>> {
+        let tree = self.raw.tree()?;
+        let key = self.codecs.encode_key(key)?;
+        let value = convert_to_pyresult(py.allow_threads(|| tree.get(key)))?;
+        Ok(self.decode_value(py, value)?.or(default))
+    }
+
+    pub fn remove(&self, py: Python, key: &PyAny) -> PyResult<Option<PyObject>> {
+        let tree = self.raw.tree()?;
+        let key = self.codecs.encode_key(key)?;
+        let old = convert_to_pyresult(py.allow_threads(|| tree.remove(key)))?;
+        self.decode_value(py, old)
+    }
+
+    pub fn contains_key(&self, py: Python, key: &PyAny) -> PyResult<bool> {
+        let tree = self.raw.tree()?;
+        let key = self.codecs.encode_key(key)?;
+        convert_to_pyresult(py.allow_threads(|| tree.contains_key(key)))
+    }
+
+    pub fn clear(&self, py: Python) -> PyResult<()> {
+        self.raw.clear(py)
+    }
+
+    pub fn is_empty(&self, py: Python) -> PyResult<bool> {
+        self.raw.is_empty(py)
+    }
+
+    pub fn len(&self, py: Python) -> PyResult<usize> {
+        self.raw.__len__(py)
+    }
+
+    /// Like `SledTree.range`, with `start` and `end` encoded by the key codec.
+    #[args(start = "None", end = "None", inclusive = "false")]
+    pub fn range(
+        &self,
+        start: Option<&PyAny>,
+        end: Option<&PyAny>,
+        inclusive: bool,
+    ) -> PyResult<SledIter> {
+        let tree = self.raw.tree()?;
+        let lo = match start {
+            Some(start) => Bound::Included(self.codecs.encode_key(start)?),
+            None => Bound::Unbounded,
+        };
+        let hi = match end {
+            Some(end) if inclusive => Bound::Included(self.codecs.encode_key(end)?),
+            Some(end) => Bound::Excluded(self.codecs.encode_key(end)?),
+            None => Bound::Unbounded,
+        };
+        Ok(self.iter(tree.range((lo, hi)), IterKind::Items))
+    }
+
+    pub fn keys(slf: PyRef<Self>, py: Python) -> SledKeysView {
+        SledKeysView::new(Self::view_source(slf, py))
+    }
+
+    pub fn values(slf: PyRef<Self>, py: Python) -> SledValuesView {
+        SledValuesView::new(Self::view_source(slf, py))
+    }
+
+    pub fn items(slf: PyRef<Self>, py: Python) -> SledItemsView {
+        SledItemsView::new(Self::view_source(slf, py))
+    }
+
+    pub fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.raw.__len__(py)
+    }
+
+    pub fn __contains__(&self, py: Python, key: &PyAny) -> PyResult<bool> {
+        self.contains_key(py, key)
+    }
+
+    pub fn __getitem__(&self, py: Python, key: &PyAny) -> PyResult<PyObject> {
+        self.get(py, key, None)?
+            .ok_or_else(|| PyKeyError::new_err(PyObject::from(key)))
+    }
+
+    pub fn __setitem__(&self, py: Python, key: &PyAny, value: &PyAny) -> PyResult<()> {
+        self.insert(py, key, value).map(|_| ())
+    }
+
+    pub fn __delitem__(&self, py: Python, key: &PyAny) -> PyResult<()> {
+        self.remove(py, key)?
+            .map(drop)
+            .ok_or_else(|| PyKeyError::new_err(PyObject::from(key)))
+    }
+
+    /// Iterates over the decoded keys, like `keys()`.
+    pub fn __iter__(&self) -> PyResult<SledIter> {
+        let tree = self.raw.tree()?;
+        Ok(self.iter(tree.iter(), IterKind::Keys))
+    }
+
+    /// Removes `key` and returns its value, or `default` if it is missing.
+    #[args(default = "*")]
+    pub fn pop(&self, py: Python, key: &PyAny, default: &PyTuple) -> PyResult<PyObject> {
+        match self.remove(py, key)? {
+            Some(value) => Ok(value),
+            None => mapping::pop_default(default, || PyKeyError::new_err(PyObject::from(key))),
+        }
+    }
+
+    /// Removes and returns the `(key, value)` pair with the smallest encoded key.
+    pub fn popitem(&self, py: Python) -> PyResult<(PyObject, PyObject)> {
+        let tree = self.raw.tree()?;
+        match convert_to_pyresult(py.allow_threads(|| tree.pop_min()))? {
+            Some((k, v)) => Ok((
+                self.codecs.decode_key(py, &k)?,
+                self.codecs.decode_value(py, &v)?,
+            )),
+            None => Err(PyKeyError::new_err("popitem(): the tree is empty")),
+        }
+    }
+
+    /// Atomically inserts `default` unless `key` is present, and returns the stored value.
+    pub fn setdefault(&self, py: Python, key: &PyAny, default: &PyAny) -> PyResult<PyObject> {
+        let tree = self.raw.tree()?;
+        let key = self.codecs.encode_key(key)?;
+        let encoded = self.codecs.encode_value(default)?;
+        let swapped =
+            py.allow_threads(|| tree.compare_and_swap(key, None as Option<&[u8]>, Some(encoded)));
+        match convert_to_pyresult(swapped)? {
+            Err(CompareAndSwapError {
+                current: Some(current),
+                ..
+            }) => self.codecs.decode_value(py, &current),
+            _ => Ok(default.into()),
+        }
+    }
+
+    /// Inserts the entries of a mapping or an iterable of `(key, value)` pairs as one batch.
+    pub fn update(&self, py: Python, other: &PyAny) -> PyResult<()> {
+        let tree = self.raw.tree()?;
+        let mut batch = Batch::default();
+        for (key, value) in mapping::pairs(other)? {
+            batch.insert(
+                self.codecs.encode_key(key)?,
+                self.codecs.encode_value(value)?,
+            );
+        }
+        convert_to_pyresult(py.allow_threads(|| tree.apply_batch(batch)))
+    }
+
+    /// Equal to any mapping with the same keys and equal decoded values.
+    pub fn __richcmp__(&self, py: Python, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        mapping::richcmp(py, op, || self.equals(py, other))
+    }
+
+    #[getter]
+    pub fn name<'p>(&self, py: Python<'p>) -> PyResult<&'p PyBytes> {
+        self.raw.name(py)
+    }
+
+    /// The same tree without codecs.
+    #[getter]
+    pub fn raw(&self) -> SledTree {
+        self.raw.clone()
+    }
+}
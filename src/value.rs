@@ -0,0 +1,54 @@
+use std::os::raw::{c_int, c_void};
+
+use pyo3::{ffi, prelude::*, types::PyBytes, AsPyPointer};
+use sled::IVec;
+
+/// A value read from sled without copying it into a Python object.
+///
+/// Exposes its bytes through the buffer protocol, so `memoryview(value)`, `bytes(value)` or
+/// `numpy.frombuffer(value, ...)` read straight from sled's buffer.
+#[pyclass]
+pub struct SledValue {
+    inner: IVec,
+}
+
+#[pymethods]
+impl SledValue {
+    unsafe fn __getbuffer__(
+        slf: PyRef<'_, Self>,
+        view: *mut ffi::Py_buffer,
+        flags: c_int,
+    ) -> PyResult<()> {
+        // the IVec never moves or changes while this object is alive, and the view keeps a
+        // reference to it
+        let buf = slf.inner.as_ptr() as *mut c_void;
+        let len = slf.inner.len() as ffi::Py_ssize_t;
+        if ffi::PyBuffer_FillInfo(view, slf.as_ptr(), buf, len, 1, flags) == -1 {
+            return Err(PyErr::fetch(slf.py()));
+        }
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+
+    pub fn __bytes__<'p>(&self, py: Python<'p>) -> &'p PyBytes {
+        PyBytes::new(py, &self.inner)
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn __repr__(&self, py: Python) -> PyResult<String> {
+        Ok(format!("SledValue({})", self.__bytes__(py).repr()?))
+    }
+}
+
+/// Converts a value read from sled into either a `SledValue` or a copy, depending on `raw`.
+pub(crate) fn into_py(py: Python, value: IVec, raw: bool) -> PyResult<PyObject> {
+    if raw {
+        Ok(Py::new(py, SledValue { inner: value })?.into_py(py))
+    } else {
+        Ok(value.to_vec().into_py(py))
+    }
+}
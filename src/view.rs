@@ -0,0 +1,342 @@
+use std::sync::Arc;
+
+use pyo3::{
+    basic::CompareOp,
+    exceptions::PyKeyError,
+    prelude::*,
+    types::{PySet, PyTuple, PyType},
+};
+
+use crate::{
+    bytes::Bytes,
+    codec::Codecs,
+    iter::{IterKind, SledIter},
+    SledTree,
+};
+
+/// The tree a view reads from. Views stay live: they see every change made to the tree after
+/// they were created.
+#[derive(Clone)]
+pub(crate) struct ViewSource {
+    // the `SledTree` or `SledTypedTree` the view was created from, used for lookups
+    mapping: PyObject,
+    raw: SledTree,
+    codecs: Option<Arc<Codecs>>,
+}
+
+impl ViewSource {
+    pub(crate) fn new(mapping: PyObject, raw: SledTree, codecs: Option<Arc<Codecs>>) -> Self {
+        Self {
+            mapping,
+            raw,
+            codecs,
+        }
+    }
+
+    fn iter(&self, kind: IterKind) -> PyResult<SledIter> {
+        let tree = self.raw.tree()?;
+        let iter = SledIter::new(&self.raw.registry, tree.iter(), kind);
+        Ok(match &self.codecs {
+            Some(codecs) => iter.with_codecs(codecs.clone()),
+            None => iter,
+        })
+    }
+
+    fn len(&self, py: Python) -> PyResult<usize> {
+        self.mapping.as_ref(py).len()
+    }
+
+    /// Compares a value yielded by an iterator with `theirs`. Raw values also equal bytes-like
+    /// objects holding the same bytes, like `==` on raw trees compares them.
+    fn value_eq(&self, ours: &PyAny, theirs: &PyAny) -> PyResult<bool> {
+        if self.codecs.is_none() {
+            if let Ok(theirs) = theirs.extract::<Bytes>() {
+                return Ok(ours.extract::<Vec<u8>>()? == *theirs);
+            }
+        }
+        ours.eq(theirs)
+    }
+
+    fn repr(&self, py: Python, name: &str) -> PyResult<String> {
+        Ok(format!("{}({})", name, self.mapping.as_ref(py).repr()?))
+    }
+
+    /// The entries as a Python `set`, for the operators of `collections.abc.Set`. Raw values are
+    /// put in as `bytes`, since the `list[int]` they are read as cannot be hashed.
+    fn to_set<'p>(&self, py: Python<'p>, kind: IterKind) -> PyResult<&'p PyAny> {
+        let kind = match kind {
+            IterKind::Items if self.codecs.is_none() => IterKind::RawItems,
+            kind => kind,
+        };
+        let iter = Py::new(py, self.iter(kind)?)?;
+        py.get_type::<PySet>().call1((iter,))
+    }
+
+    /// Applies the operator `op` of Python's `set` to the view and an iterable, with the view on
+    /// the right if `reflected`, and returns a `set` like the views of `dict` do.
+    fn set_op(
+        &self,
+        py: Python,
+        kind: IterKind,
+        other: &PyAny,
+        op: &str,
+        reflected: bool,
+    ) -> PyResult<PyObject> {
+        if other.iter().is_err() {
+            return Ok(py.NotImplemented());
+        }
+        let ours = self.to_set(py, kind)?;
+        let other = py.get_type::<PySet>().call1((other,))?;
+        let (left, right) = if reflected {
+            (other, ours)
+        } else {
+            (ours, other)
+        };
+        Ok(left.call_method1(op, (right,))?.into())
+    }
+
+    /// Compares the view with another `collections.abc.Set`, as subset, superset or equal.
+    fn set_richcmp(
+        &self,
+        py: Python,
+        kind: IterKind,
+        other: &PyAny,
+        op: CompareOp,
+    ) -> PyResult<PyObject> {
+        if !is_set(other)? {
+            return Ok(py.NotImplemented());
+        }
+        let other = py.get_type::<PySet>().call1((other,))?;
+        Ok(self.to_set(py, kind)?.rich_compare(other, op)?.into())
+    }
+
+    fn isdisjoint(&self, py: Python, kind: IterKind, other: &PyAny) -> PyResult<bool> {
+        self.to_set(py, kind)?
+            .call_method1("isdisjoint", (other,))?
+            .extract()
+    }
+}
+
+fn is_set(other: &PyAny) -> PyResult<bool> {
+    let set = other.py().import("collections.abc")?.getattr("Set")?;
+    other.is_instance(set.downcast::<PyType>()?)
+}
+
+/// A live view of the keys of a tree, returned by `keys()`. Supports `len()`, `in`, iteration
+/// and the set operators of `dict.keys()`, which return a `set`.
+#[pyclass]
+pub struct SledKeysView {
+    source: ViewSource,
+}
+
+impl SledKeysView {
+    pub(crate) fn new(source: ViewSource) -> Self {
+        Self { source }
+    }
+}
+
+#[pymethods]
+impl SledKeysView {
+    pub fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.source.len(py)
+    }
+
+    pub fn __iter__(&self) -> PyResult<SledIter> {
+        self.source.iter(IterKind::Keys)
+    }
+
+    pub fn __reversed__(&self) -> PyResult<SledIter> {
+        Ok(self.source.iter(IterKind::Keys)?.__reversed__())
+    }
+
+    pub fn __contains__(&self, py: Python, key: &PyAny) -> PyResult<bool> {
+        self.source.mapping.as_ref(py).contains(key)
+    }
+
+    pub fn __repr__(&self, py: Python) -> PyResult<String> {
+        self.source.repr(py, "SledKeysView")
+    }
+
+    pub fn __and__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__and__", false)
+    }
+
+    pub fn __rand__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__and__", true)
+    }
+
+    pub fn __or__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__or__", false)
+    }
+
+    pub fn __ror__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__or__", true)
+    }
+
+    pub fn __sub__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__sub__", false)
+    }
+
+    pub fn __rsub__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__sub__", true)
+    }
+
+    pub fn __xor__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__xor__", false)
+    }
+
+    pub fn __rxor__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Keys, other, "__xor__", true)
+    }
+
+    pub fn __richcmp__(&self, py: Python, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        self.source.set_richcmp(py, IterKind::Keys, other, op)
+    }
+
+    pub fn isdisjoint(&self, py: Python, other: &PyAny) -> PyResult<bool> {
+        self.source.isdisjoint(py, IterKind::Keys, other)
+    }
+}
+
+/// A live view of the values of a tree, returned by `values()`. Supports `len()`, `in` and
+/// iteration.
+#[pyclass]
+pub struct SledValuesView {
+    source: ViewSource,
+}
+
+impl SledValuesView {
+    pub(crate) fn new(source: ViewSource) -> Self {
+        Self { source }
+    }
+}
+
+#[pymethods]
+impl SledValuesView {
+    pub fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.source.len(py)
+    }
+
+    pub fn __iter__(&self) -> PyResult<SledIter> {
+        self.source.iter(IterKind::Values)
+    }
+
+    pub fn __reversed__(&self) -> PyResult<SledIter> {
+        Ok(self.source.iter(IterKind::Values)?.__reversed__())
+    }
+
+    /// Scans the values in key order until one is equal to `value`.
+    pub fn __contains__(&self, py: Python, value: &PyAny) -> PyResult<bool> {
+        let iter = Py::new(py, self.source.iter(IterKind::Values)?)?;
+        for ours in iter.as_ref(py).iter()? {
+            if self.source.value_eq(ours?, value)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    pub fn __repr__(&self, py: Python) -> PyResult<String> {
+        self.source.repr(py, "SledValuesView")
+    }
+}
+
+/// A live view of the `(key, value)` pairs of a tree, returned by `items()`. Supports `len()`,
+/// `in`, iteration and the set operators of `dict.items()`, which return a `set`.
+#[pyclass]
+pub struct SledItemsView {
+    source: ViewSource,
+}
+
+impl SledItemsView {
+    pub(crate) fn new(source: ViewSource) -> Self {
+        Self { source }
+    }
+}
+
+#[pymethods]
+impl SledItemsView {
+    pub fn __len__(&self, py: Python) -> PyResult<usize> {
+        self.source.len(py)
+    }
+
+    pub fn __iter__(&self) -> PyResult<SledIter> {
+        self.source.iter(IterKind::Items)
+    }
+
+    pub fn __reversed__(&self) -> PyResult<SledIter> {
+        Ok(self.source.iter(IterKind::Items)?.__reversed__())
+    }
+
+    pub fn __contains__(&self, py: Python, item: &PyAny) -> PyResult<bool> {
+        let (key, value) = match item.downcast::<PyTuple>() {
+            Ok(item) if item.len() == 2 => (item.get_item(0)?, item.get_item(1)?),
+            _ => return Ok(false),
+        };
+        match self.source.mapping.as_ref(py).get_item(key) {
+            Ok(ours) => self.source.value_eq(ours, value),
+            Err(e) if e.is_instance_of::<PyKeyError>(py) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn __repr__(&self, py: Python) -> PyResult<String> {
+        self.source.repr(py, "SledItemsView")
+    }
+
+    pub fn __and__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__and__", false)
+    }
+
+    pub fn __rand__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__and__", true)
+    }
+
+    pub fn __or__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__or__", false)
+    }
+
+    pub fn __ror__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__or__", true)
+    }
+
+    pub fn __sub__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__sub__", false)
+    }
+
+    pub fn __rsub__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__sub__", true)
+    }
+
+    pub fn __xor__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__xor__", false)
+    }
+
+    pub fn __rxor__(&self, py: Python, other: &PyAny) -> PyResult<PyObject> {
+        self.source
+            .set_op(py, IterKind::Items, other, "__xor__", true)
+    }
+
+    pub fn __richcmp__(&self, py: Python, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        self.source.set_richcmp(py, IterKind::Items, other, op)
+    }
+
+    pub fn isdisjoint(&self, py: Python, other: &PyAny) -> PyResult<bool> {
+        self.source.isdisjoint(py, IterKind::Items, other)
+    }
+}
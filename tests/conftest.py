@@ -0,0 +1,46 @@
+"""Needs an installed build, e.g. `maturin develop && python -m pytest tests`."""
+import faulthandler
+import threading
+from concurrent.futures import ThreadPoolExecutor, wait
+
+import pytest
+
+# a thread stuck in Rust while holding the GIL cannot be interrupted from Python
+WATCHDOG_SECONDS = 30
+
+
+@pytest.fixture
+def watchdog():
+    """Kills the process with a traceback of every thread if the test deadlocks."""
+    faulthandler.dump_traceback_later(WATCHDOG_SECONDS, exit=True)
+    yield
+    faulthandler.cancel_dump_traceback_later()
+
+
+@pytest.fixture
+def behind_transaction(watchdog):
+    """Runs `op(db)` on another thread while a transaction holds sled's write lock and returns
+    its result.
+
+    Reads block inside sled until the transaction ends, which needs this thread. If the read kept
+    the GIL while blocked, neither could continue and the watchdog kills the process.
+    """
+
+    def run(db, op):
+        entered, release = threading.Event(), threading.Event()
+
+        def hold(_tree):
+            entered.set()
+            release.wait()
+
+        with ThreadPoolExecutor(2) as pool:
+            holder = pool.submit(db.transaction, hold)
+            entered.wait()
+            reader = pool.submit(op, db)
+            # gives the read time to block in sled, returning at all needs the GIL
+            wait([reader], timeout=0.2)
+            release.set()
+            holder.result()
+            return reader.result()
+
+    return run
@@ -0,0 +1,389 @@
+//! Checks that the stubs in `pysled/` declare exactly what the extension module exports, down to the
+//! parameter names, so the stubs cannot drift from the `#[pymethods]` and `#[pyfunction]`s.
+//!
+//! The sources are read as text, since the `cdylib` cannot be linked into a test.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// Submodules built in Rust, as (source file, module name), each stubbed in `pysled/<name>.pyi`.
+const SUBMODULES: &[(&str, &str)] = &[("keys.rs", "keys")];
+
+/// Parameter names of a function or method, or `None` for an attribute.
+type Params = Option<Vec<String>>;
+
+/// Members of each scope, where the module itself is the scope `""`.
+#[derive(Default)]
+struct Api {
+    classes: BTreeSet<String>,
+    members: BTreeMap<String, BTreeMap<String, Vec<Params>>>,
+}
+
+impl Api {
+    fn add(&mut self, scope: &str, name: &str, params: Params) {
+        self.members
+            .entry(scope.to_owned())
+            .or_default()
+            .entry(name.to_owned())
+            .or_default()
+            .push(params);
+    }
+}
+
+/// The text between the parenthesis at `open` and its matching closing one.
+fn parenthesized(text: &str, open: usize) -> &str {
+    let mut depth = 0;
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return &text[open + 1..open + i];
+                }
+            }
+            _ => {}
+        }
+    }
+    panic!("unbalanced parentheses in {:?}", &text[open..]);
+}
+
+/// Splits on commas that are not nested in brackets or generics.
+fn split_top_level(params: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in params.char_indices() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&params[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&params[start..]);
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+/// The Python-visible parameters of a Rust function, without `self`, `slf` and the GIL token.
+fn rust_params(params: &str) -> Vec<String> {
+    split_top_level(params)
+        .into_iter()
+        .filter_map(|param| {
+            let (name, ty) = param.split_once(':')?;
+            let name = name.trim().trim_start_matches("mut ").trim();
+            let ty = ty.trim();
+            let implicit = ty.starts_with("Python") || ty.starts_with("PyRef");
+            (!implicit).then(|| name.to_owned())
+        })
+        .collect()
+}
+
+fn stub_params(params: &str, is_method: bool) -> Vec<String> {
+    let mut names: Vec<String> = split_top_level(params)
+        .into_iter()
+        .filter(|p| *p != "*" && *p != "/")
+        .map(|p| {
+            let end = p.find([':', '=']).unwrap_or(p.len());
+            p[..end].trim().trim_start_matches('*').to_owned()
+        })
+        .collect();
+    if is_method && matches!(names.first().map(String::as_str), Some("self" | "cls")) {
+        names.remove(0);
+    }
+    names
+}
+
+fn read_sources() -> BTreeMap<String, String> {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    fs::read_dir(src)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect()
+}
+
+/// The Python-visible parameters of the `#[pyfunction]` called `name`.
+fn pyfunction_params(sources: &BTreeMap<String, String>, name: &str) -> Vec<String> {
+    for source in sources.values() {
+        for (at, _) in source.match_indices("#[pyfunction]") {
+            let start = at + source[at..].find("fn ").unwrap() + 3;
+            let rest = &source[start..];
+            if rest[..rest.find(['(', '<']).unwrap()] == *name {
+                return rust_params(parenthesized(source, start + rest.find('(').unwrap()));
+            }
+        }
+    }
+    panic!("no #[pyfunction] named {}", name);
+}
+
+/// Maps the Rust struct name of every `#[pyclass]` to its Python name and exposed fields.
+fn pyclasses(sources: &BTreeMap<String, String>) -> BTreeMap<String, (String, Vec<String>)> {
+    let mut classes = BTreeMap::new();
+    for source in sources.values() {
+        for (at, _) in source.match_indices("#[pyclass") {
+            let attr = &source[at..at + source[at..].find('\n').unwrap()];
+            let decl = &source[at + source[at..].find("struct ").unwrap() + 7..];
+            let rust_name: String = decl.chars().take_while(|c| c.is_alphanumeric()).collect();
+            let py_name = attr
+                .split_once("name = \"")
+                .map(|(_, rest)| rest[..rest.find('"').unwrap()].to_owned())
+                .unwrap_or_else(|| rust_name.clone());
+            let body = match decl.find('{') {
+                Some(open) if open < decl.find(';').unwrap_or(usize::MAX) => {
+                    &decl[open..open + decl[open..].find("\n}").unwrap()]
+                }
+                _ => "",
+            };
+            let fields = body
+                .split("#[pyo3(get)]")
+                .skip(1)
+                .map(|field| {
+                    let field = field.trim().trim_start_matches("pub ");
+                    field[..field.find(':').unwrap()].to_owned()
+                })
+                .collect();
+            classes.insert(rust_name, (py_name, fields));
+        }
+    }
+    classes
+}
+
+fn exported_api() -> Api {
+    let sources = read_sources();
+    let lib = &sources["lib.rs"];
+    let classes = pyclasses(&sources);
+    let mut api = Api::default();
+
+    for line in lib.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("m.add_class::<") {
+            let (py_name, fields) = &classes[&rest[..rest.find('>').unwrap()]];
+            api.classes.insert(py_name.clone());
+            for field in fields {
+                api.add(py_name, field, None);
+            }
+        } else if let Some(rest) = line.strip_prefix("m.add(\"") {
+            api.add("", &rest[..rest.find('"').unwrap()], None);
+        }
+    }
+
+    let mut modules = vec![("lib.rs", "")];
+    for &(file, module) in SUBMODULES {
+        modules.push((file, module));
+        api.add("", module, None);
+    }
+    for (file, scope) in modules {
+        for (at, _) in sources[file].match_indices("wrap_pyfunction!(") {
+            let line = &sources[file][at..at + sources[file][at..].find('\n').unwrap()];
+            if !sources[file][..at].ends_with("m.add_function(") {
+                continue;
+            }
+            let path = &line["wrap_pyfunction!(".len()..line.find(',').unwrap()];
+            let name = path.rsplit("::").next().unwrap();
+            api.add(scope, name, Some(pyfunction_params(&sources, name)));
+        }
+    }
+
+    for source in sources.values() {
+        for (at, _) in source.match_indices("#[pymethods]\nimpl ") {
+            let header = &source[at + "#[pymethods]\nimpl ".len()..];
+            let rust_name = &header[..header.find(' ').unwrap()];
+            let (py_name, _) = &classes[rust_name];
+            if !api.classes.contains(py_name) {
+                continue;
+            }
+            let body = &header[..header.find("\n}\n").unwrap()];
+            let mut attrs = String::new();
+            let mut offset = 0;
+            for line in body.split_inclusive('\n') {
+                let start = offset;
+                offset += line.len();
+                let Some(item) = line.strip_prefix("    ").filter(|l| !l.starts_with(' ')) else {
+                    continue;
+                };
+                if item.starts_with("#[") || !attrs.is_empty() && !attrs.ends_with(']') {
+                    attrs.push_str(item.trim());
+                    continue;
+                }
+                let Some(fn_at) = item.find("fn ").filter(|_| !item.starts_with("//")) else {
+                    continue;
+                };
+                let rest = &item[fn_at + 3..];
+                let name = &rest[..rest.find(['(', '<']).unwrap()];
+                let open = start + 4 + fn_at + 3 + rest.find('(').unwrap();
+                let params = rust_params(parenthesized(body, open));
+                match name {
+                    // buffer protocol slots have no Python-visible method
+                    "__getbuffer__" | "__releasebuffer__" => {}
+                    "__richcmp__" => {
+                        api.add(py_name, "__eq__", Some(vec!["other".into()]));
+                        api.add(py_name, "__ne__", Some(vec!["other".into()]));
+                    }
+                    _ if attrs.contains("#[new]") => api.add(py_name, "__init__", Some(params)),
+                    _ => api.add(py_name, name, Some(params)),
+                }
+                attrs.clear();
+            }
+        }
+    }
+    api
+}
+
+fn stub_api() -> Api {
+    let package = Path::new(env!("CARGO_MANIFEST_DIR")).join("pysled");
+    let mut api = Api::default();
+    read_stub(&mut api, &package.join("__init__.pyi"), "");
+    for &(_, module) in SUBMODULES {
+        read_stub(&mut api, &package.join(format!("{}.pyi", module)), module);
+    }
+    api
+}
+
+/// Adds the declarations of a stub file, with its module level ones in the scope `module`.
+fn read_stub(api: &mut Api, path: &Path, module: &str) {
+    let stub = fs::read_to_string(path).unwrap();
+    let mut scope = String::new();
+    let mut offset = 0;
+    for line in stub.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        // members are indented once, deeper lines continue a signature
+        let (member, text) = match line.strip_prefix("    ") {
+            Some(text) if !text.starts_with(' ') => (true, text.trim()),
+            Some(_) => continue,
+            None if line.starts_with(' ') => continue,
+            None => (false, line.trim()),
+        };
+        if !member && !text.is_empty() {
+            scope.clear();
+        }
+        let owner = if member {
+            scope.clone()
+        } else {
+            module.to_owned()
+        };
+        if let Some(rest) = text.strip_prefix("class ") {
+            let name: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            if !name.starts_with('_') {
+                api.classes.insert(name.clone());
+            }
+            scope = name;
+        } else if let Some(rest) = text.strip_prefix("def ") {
+            let name = &rest[..rest.find('(').unwrap()];
+            let open = start + line.find('(').unwrap();
+            let params = stub_params(parenthesized(&stub, open), member);
+            api.add(&owner, name, Some(params));
+        } else if let Some(rest) = text.strip_prefix("from . import ") {
+            // submodules are re-exported as `from . import keys as keys`
+            if let Some((name, alias)) = rest.split_once(" as ") {
+                if name == alias {
+                    api.add(&owner, name, None);
+                }
+            }
+        } else if let Some((name, _)) = text.split_once(": ") {
+            // private aliases start with one underscore, module dunders like `__version__` count
+            let public = !name.starts_with('_') || name.starts_with("__");
+            if public && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                api.add(&owner, name, None);
+            }
+        }
+    }
+}
+
+#[test]
+fn stubs_match_exported_api() {
+    let exported = exported_api();
+    let stub = stub_api();
+    let mut problems = vec![];
+
+    let stub_names: BTreeSet<_> = stub.classes.iter().chain(stub.members[""].keys()).collect();
+    for class in &exported.classes {
+        if !stub.classes.contains(class) {
+            problems.push(format!("class {} is missing from the stub", class));
+        }
+    }
+    for class in &stub.classes {
+        let exported_as_attr = exported.members[""].contains_key(class);
+        if !exported.classes.contains(class) && !exported_as_attr {
+            problems.push(format!("class {} in the stub is not exported", class));
+        }
+    }
+
+    let empty = BTreeMap::new();
+    let scopes: BTreeSet<_> = exported.members.keys().chain(stub.members.keys()).collect();
+    for scope in scopes {
+        // exception types get their attributes with `setattr` when they are raised
+        let submodule = SUBMODULES.iter().any(|&(_, module)| module == scope);
+        let exception = !exported.classes.contains(scope)
+            && !submodule
+            && exported.members[""].contains_key(scope);
+        if exception {
+            continue;
+        }
+        let ours = exported.members.get(scope).unwrap_or(&empty);
+        let theirs = stub.members.get(scope).unwrap_or(&empty);
+        let owner = match scope.as_str() {
+            "" => "pysled".to_owned(),
+            _ if submodule => format!("pysled.{}", scope),
+            _ => scope.clone(),
+        };
+        for (name, signatures) in ours {
+            // module level exports may be declared as classes, like the exception types
+            if scope.is_empty() && signatures == &[None] && stub_names.contains(name) {
+                continue;
+            }
+            let Some(declared) = theirs.get(name) else {
+                problems.push(format!("{}.{} is missing from the stub", owner, name));
+                continue;
+            };
+            let expected = &signatures[0];
+            // dunder methods are called by Python itself, so their parameter names do not matter
+            let dunder = name.starts_with("__") && name != "__init__";
+            let matches = match expected {
+                Some(_) if dunder => declared.iter().all(Option::is_some),
+                Some(expected) => {
+                    // overloads may leave out trailing parameters, but one has to list them all
+                    declared
+                        .iter()
+                        .all(|d| d.as_ref().is_some_and(|d| expected.starts_with(d)))
+                        && declared.iter().any(|d| d.as_ref() == Some(expected))
+                }
+                None => declared.iter().all(Option::is_none),
+            };
+            if !matches {
+                problems.push(format!(
+                    "{}.{} is exported as {:?} but declared as {:?}",
+                    owner, name, expected, declared
+                ));
+            }
+        }
+        for name in theirs.keys() {
+            let exported_as_class = scope.is_empty() && exported.classes.contains(name);
+            if !ours.contains_key(name) && !exported_as_class {
+                problems.push(format!("{}.{} in the stub is not exported", owner, name));
+            }
+        }
+    }
+
+    assert!(
+        problems.is_empty(),
+        "the stubs in pysled/ are out of date:\n{}",
+        problems.join("\n")
+    );
+}
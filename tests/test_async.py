@@ -0,0 +1,81 @@
+import asyncio
+import threading
+import time
+
+import pysled
+
+
+def test_flush_async(tmp_path):
+    # no background flushes, so the one awaited here writes the insert
+    db = pysled.SledConfig().path(tmp_path / "db").flush_every_ms(None).open()
+    db.insert(b"k", b"v" * 1000)
+
+    async def flush():
+        return await db.flush_async()
+
+    assert asyncio.run(flush()) > 0
+    assert asyncio.run(flush()) == 0
+
+
+def test_async_for(watchdog):
+    db = pysled.SledDb.in_memory()
+    subscriber = db.watch_prefix(b"")
+
+    async def main():
+        db.insert(b"a", b"1")
+        db.remove(b"a")
+        events = []
+        async for event in subscriber:
+            events.append(event)
+            if len(events) == 2:
+                return events
+
+    insert, remove = asyncio.run(main())
+    assert isinstance(insert, pysled.InsertEvent) and (insert.key, insert.value) == (b"a", [49])
+    assert isinstance(remove, pysled.RemoveEvent) and remove.key == b"a"
+
+
+def test_cancelled_wait_keeps_event(watchdog):
+    db = pysled.SledDb.in_memory()
+    subscriber = db.watch_prefix(b"")
+
+    async def main():
+        waiting = subscriber.__anext__()
+        db.insert(b"a", b"1")
+        # blocks the loop, so the event is received before the loop sees the cancellation
+        time.sleep(0.2)
+        waiting.cancel()
+        return await asyncio.wait_for(subscriber.__anext__(), 5)
+
+    assert asyncio.run(main()).key == b"a"
+
+
+def test_blocking_and_async_reads(watchdog):
+    db = pysled.SledDb.in_memory()
+    subscriber = db.watch_prefix(b"")
+    other = db.watch_prefix(b"")
+
+    async def main():
+        loop = asyncio.get_running_loop()
+        blocking = loop.run_in_executor(None, lambda: subscriber.next(timeout=5))
+        waiting = asyncio.ensure_future(subscriber.__anext__())
+        await asyncio.sleep(0.1)
+        # a blocked reader must not hold up async work elsewhere
+        await asyncio.wait_for(db.flush_async(), 1)
+        db.insert(b"a", b"1")
+        assert (await asyncio.wait_for(other.__anext__(), 1)).key == b"a"
+        db.insert(b"b", b"2")
+        return await asyncio.wait_for(asyncio.gather(blocking, waiting), 5)
+
+    assert sorted(event.key for event in asyncio.run(main())) == [b"a", b"b"]
+
+
+def test_async_for_ends_on_close(watchdog):
+    db = pysled.SledDb.in_memory()
+    subscriber = db.watch_prefix(b"")
+
+    async def main():
+        threading.Timer(0.1, db.close).start()
+        return [event async for event in subscriber]
+
+    assert asyncio.run(main()) == []
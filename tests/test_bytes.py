@@ -0,0 +1,56 @@
+import pytest
+
+import pysled
+
+
+@pytest.fixture
+def strict():
+    pysled.set_strict_bytes(True)
+    yield
+    pysled.set_strict_bytes(False)
+
+
+@pytest.mark.parametrize("key", [b"k", bytearray(b"k"), memoryview(b"k"), "k"])
+def test_accepted_keys(key):
+    db = pysled.SledDb.in_memory()
+    db.insert(key, b"v")
+    assert db[b"k"] == list(b"v")
+
+
+def test_values_read_can_be_written_back():
+    db = pysled.SledDb.in_memory()
+    db[b"a"] = b"1"
+    db[b"b"] = db[b"a"]
+    db.compare_and_swap(b"a", db.get(b"a"), b"2")
+    other = pysled.SledDb.in_memory()
+    other.update(dict(db))
+    assert dict(other) == {b"a": list(b"2"), b"b": list(b"1")}
+
+
+@pytest.mark.parametrize("value", [104, 1.5, None, [256]])
+def test_other_values_are_rejected(value):
+    db = pysled.SledDb.in_memory()
+    with pytest.raises((TypeError, OverflowError)):
+        db.insert(b"k", value)
+
+
+def test_strict_mode_rejects_str(strict):
+    db = pysled.SledDb.in_memory()
+    with pytest.raises(TypeError, match="strict mode"):
+        db.insert("k", b"v")
+    db.insert(b"k", b"v")
+
+
+def test_import_accepts_bytes_like_and_str():
+    db = pysled.SledDb.in_memory()
+    db.import_([("tree", "t", [("a", bytearray(b"1")), (memoryview(b"b"), b"2")])])
+    assert dict(db.open_tree(b"t")) == {b"a": [49], b"b": [50]}
+
+
+def test_export_round_trips_through_import():
+    db = pysled.SledDb.in_memory()
+    db.update({b"a": b"1"})
+    db.open_tree(b"t").update({b"b": b"2"})
+    copy = pysled.SledDb.in_memory()
+    copy.import_(db.export())
+    assert copy == db and copy.open_tree(b"t") == db.open_tree(b"t")
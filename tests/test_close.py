@@ -0,0 +1,50 @@
+import pytest
+
+import pysled
+
+
+def test_reopen_after_close(tmp_path):
+    path = str(tmp_path / "db")
+    db = pysled.SledDb(path)
+    db.insert(b"k", b"v")
+    with pytest.raises(pysled.IoError, match="could not acquire lock"):
+        pysled.SledDb(path)
+    # the first object is still referenced, so only close() releases the lock on the files
+    db.close()
+    reopened = pysled.SledDb(path)
+    assert reopened[b"k"] == [118]
+    reopened.close()
+
+
+def test_with_block_closes(tmp_path):
+    path = str(tmp_path / "db")
+    with pysled.SledDb(path) as db:
+        db.insert(b"k", b"v")
+    with pytest.raises(pysled.SledClosedError):
+        db.get(b"k")
+    with pysled.SledDb(path) as db:
+        assert db[b"k"] == [118]
+
+
+def test_with_block_closes_on_error(tmp_path):
+    with pytest.raises(KeyError):
+        with pysled.SledDb(str(tmp_path / "db")) as db:
+            db[b"missing"]
+    with pytest.raises(pysled.SledClosedError):
+        len(db)
+
+
+def test_handles_raise_after_close():
+    db = pysled.SledDb.in_memory()
+    db.update({b"a": b"1", b"b": b"2"})
+    tree = db.open_tree(b"t")
+    items = iter(db.items())
+    subscriber = db.watch_prefix(b"")
+    assert next(items) == (b"a", [49])
+    db.close()
+    for use in [lambda: db.insert(b"c", b"3"), lambda: tree.get(b"a"), lambda: next(items)]:
+        with pytest.raises(pysled.SledClosedError):
+            use()
+    assert next(subscriber, None) is None
+    # closing twice does nothing
+    db.close()
@@ -0,0 +1,20 @@
+import pytest
+
+import pysled
+
+
+def test_errors_name_the_key_or_value():
+    db = pysled.SledDb.in_memory()
+    tree = db.open_tree(b"typed", key_codec="str", value_codec="int")
+    with pytest.raises(pysled.CodecError, match="^Failed to encode key: "):
+        tree.get(5)
+    with pytest.raises(pysled.CodecError, match="^Failed to encode value: "):
+        tree.insert("a", "not an int")
+
+    db.open_tree(b"typed").insert(b"\xff", b"short")
+    with pytest.raises(pysled.CodecError, match="^Failed to decode key: "):
+        list(tree.keys())
+    db.open_tree(b"typed").remove(b"\xff")
+    db.open_tree(b"typed").insert(b"a", b"short")
+    with pytest.raises(pysled.CodecError, match="^Failed to decode value: "):
+        tree["a"]
@@ -0,0 +1,119 @@
+import errno
+import struct
+
+import pytest
+
+import pysled
+
+
+def _record(data):
+    return struct.pack(">I", len(data)) + data
+
+
+# format version 1 as written by dump_to, which later releases must keep loading
+DUMP_V1 = (
+    b"PYSLEDDB"
+    + struct.pack(">I", 1)
+    + b"\x01" + _record(b"tree") + _record(b"__sled__default")
+    + b"\x02" + _record(b"a") + _record(b"1")
+    + b"\x01" + _record(b"tree") + _record(b"other")
+    + b"\x02" + _record(b"\x00\xff") + _record(b"")
+    + b"\x02" + _record(b"b") + _record(b"2" * 300)
+    + b"\x00"
+)
+
+
+def _contents(db):
+    return {bytes(tree.name): [(k, bytes(v)) for k, v in tree.items()] for tree in db.trees()}
+
+
+def _fill(db):
+    db.insert(b"a", b"1")
+    other = db.open_tree(b"other")
+    other.insert(b"\x00\xff", b"")
+    other.insert(b"b", b"2" * 300)
+
+
+def test_round_trip(tmp_path):
+    db = pysled.SledDb.in_memory()
+    _fill(db)
+    db.dump_to(tmp_path / "dump")
+
+    loaded = pysled.SledDb.in_memory()
+    loaded.load_from(tmp_path / "dump")
+    assert _contents(loaded) == _contents(db)
+
+
+def test_format_v1(tmp_path):
+    db = pysled.SledDb.in_memory()
+    _fill(db)
+    db.dump_to(tmp_path / "dump")
+    assert (tmp_path / "dump").read_bytes() == DUMP_V1
+
+    (tmp_path / "v1").write_bytes(DUMP_V1)
+    loaded = pysled.SledDb.in_memory()
+    loaded.load_from(tmp_path / "v1")
+    assert _contents(loaded) == _contents(db)
+
+
+def test_dump_refuses_existing_file(tmp_path):
+    path = tmp_path / "dump"
+    path.write_bytes(b"keep me")
+    db = pysled.SledDb.in_memory()
+    with pytest.raises(pysled.IoError) as e:
+        db.dump_to(path)
+    assert e.value.errno == errno.EEXIST
+    assert path.read_bytes() == b"keep me"
+
+
+@pytest.mark.parametrize("cut", [len(b"PYSLEDDB") + 4, 20, len(DUMP_V1) // 2, len(DUMP_V1) - 1])
+def test_load_truncated(tmp_path, cut):
+    (tmp_path / "dump").write_bytes(DUMP_V1[:cut])
+    db = pysled.SledDb.in_memory()
+    with pytest.raises(ValueError, match="truncated"):
+        db.load_from(tmp_path / "dump")
+    assert _contents(db) == {b"__sled__default": []}
+
+    # nothing was kept, so the intact file loads afterwards
+    (tmp_path / "dump").write_bytes(DUMP_V1)
+    db.load_from(tmp_path / "dump")
+    assert b"other" in db.tree_names()
+
+
+def test_load_truncated_after_several_batches(tmp_path):
+    db = pysled.SledDb.in_memory()
+    db.update({b"%05d" % i: b"v" for i in range(3000)})
+    db.dump_to(tmp_path / "dump")
+    (tmp_path / "cut").write_bytes((tmp_path / "dump").read_bytes()[:-20])
+
+    loaded = pysled.SledDb.in_memory()
+    with pytest.raises(ValueError, match="truncated"):
+        loaded.load_from(tmp_path / "cut")
+    assert len(loaded) == 0
+    loaded.load_from(tmp_path / "dump")
+    assert len(loaded) == 3000
+
+
+def test_failed_import_keeps_nothing():
+    def items():
+        yield b"a", b"1"
+        raise RuntimeError("source went away")
+
+    db = pysled.SledDb.in_memory()
+    collections = [(b"tree", b"first", [(b"x", b"1")] * 2000), (b"tree", b"second", items())]
+    with pytest.raises(RuntimeError, match="source went away"):
+        db.import_(collections)
+    assert db.tree_names() == [b"__sled__default"]
+
+    db.import_([(b"tree", b"first", [(b"x", b"1")])])
+    assert dict(db.open_tree(b"first")) == {b"x": [49]}
+
+
+def test_load_rejects_other_versions_and_files(tmp_path):
+    db = pysled.SledDb.in_memory()
+    (tmp_path / "v2").write_bytes(b"PYSLEDDB" + struct.pack(">I", 2) + b"\x00")
+    with pytest.raises(ValueError, match="version 2"):
+        db.load_from(tmp_path / "v2")
+    (tmp_path / "other").write_bytes(b"not a dump")
+    with pytest.raises(ValueError, match="Not a pysled dump file"):
+        db.load_from(tmp_path / "other")
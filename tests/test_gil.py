@@ -0,0 +1,19 @@
+import pytest
+
+import pysled
+
+READS = {
+    "get": lambda db: db.get(b"k"),
+    "len": len,
+    "contains_key": lambda db: db.contains_key(b"k"),
+    "iteration": lambda db: list(db),
+    "range": lambda db: list(db.range(b"a", b"z")),
+    "first": lambda db: db.first(),
+}
+
+
+@pytest.mark.parametrize("read", READS.values(), ids=READS.keys())
+def test_reads_release_the_gil(behind_transaction, read):
+    db = pysled.SledDb.in_memory()
+    db.insert(b"k", b"v")
+    assert behind_transaction(db, read) == read(db)
@@ -0,0 +1,30 @@
+import pytest
+
+from pysled import keys
+
+
+def _nested(depth):
+    t = ()
+    for _ in range(depth):
+        t = (t,)
+    return t
+
+
+def test_round_trip():
+    items = (None, False, True, -(2**64 - 1), 0, 2**64 - 1, -0.0, 1.5, "a\0b", b"\0\xff", (None, (1,)))
+    assert keys.unpack(keys.pack(items)) == items
+
+
+def test_deep_nesting_raises_instead_of_crashing():
+    assert keys.unpack(keys.pack((_nested(255),))) == (_nested(255),)
+    with pytest.raises(ValueError):
+        keys.pack((_nested(200_000),))
+    with pytest.raises(ValueError):
+        keys.unpack(b"\x05" * 500_000)
+
+
+def test_out_of_range_ints():
+    with pytest.raises(OverflowError):
+        keys.pack((2**64,))
+    with pytest.raises(OverflowError):
+        keys.pack((-(2**64),))
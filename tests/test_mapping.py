@@ -0,0 +1,125 @@
+import threading
+import time
+from collections.abc import ItemsView, KeysView, Mapping, ValuesView
+
+import pysled
+
+
+class _StartsTransaction(Mapping):
+    """A mapping whose first lookup starts a transaction on `db` and returns once its callback
+    runs, so the comparison goes on reading while the transaction holds sled's write lock."""
+
+    def __init__(self, db, data):
+        self.db = db
+        self.data = data
+        self.entered = threading.Event()
+        self.holder = None
+
+    def _hold(self, _tree):
+        self.entered.set()
+        # needs the GIL again to finish, while the comparison waits for sled's lock
+        time.sleep(0.1)
+
+    def __getitem__(self, key):
+        if self.holder is None:
+            self.holder = threading.Thread(target=self.db.transaction, args=(self._hold,))
+            self.holder.start()
+            self.entered.wait()
+        return self.data[key]
+
+    def __iter__(self):
+        return iter(self.data)
+
+    def __len__(self):
+        return len(self.data)
+
+
+def test_eq_during_transaction(watchdog):
+    db = pysled.SledDb.in_memory()
+    db.update({bytes([i]): b"v" for i in range(100)})
+    other = _StartsTransaction(db, {bytes([i]): b"v" for i in range(100)})
+    assert db == other
+    other.holder.join()
+
+
+def test_typed_eq_during_transaction(watchdog):
+    db = pysled.SledDb.in_memory()
+    tree = db.open_tree(b"typed", key_codec="str", value_codec="json")
+    tree.update({str(i): [i] for i in range(100)})
+    other = _StartsTransaction(db, {str(i): [i] for i in range(100)})
+    assert tree == other
+    other.holder.join()
+
+
+def _filled():
+    db = pysled.SledDb.in_memory()
+    db.update({b"a": b"1", b"b": b"2", b"c": b"3"})
+    return db
+
+
+def test_keys_are_bytes_everywhere():
+    db = _filled()
+    sub = db.watch_prefix(b"")
+    db.insert(b"d", b"4")
+    db.remove(b"d")
+    keys = [
+        next(iter(db)),
+        db.first()[0],
+        db.last()[0],
+        db.get_gt(b"a")[0],
+        db.floor(b"b")[0],
+        db.all()[0][0],
+        db.pop_min_n(1)[0][0],
+        db.popitem()[0],
+        next(sub).key,
+        next(sub).key,
+        db.name,
+    ]
+    assert all(type(key) is bytes for key in keys), keys
+
+
+def test_views():
+    db = _filled()
+    keys, values, items = db.keys(), db.values(), db.items()
+    assert isinstance(keys, KeysView)
+    assert isinstance(values, ValuesView)
+    assert isinstance(items, ItemsView)
+    assert len(keys) == len(values) == len(items) == 3
+    assert list(keys) == [b"a", b"b", b"c"]
+    assert list(reversed(items)) == [(b"c", [51]), (b"b", [50]), (b"a", [49])]
+    assert b"a" in keys and b"z" not in keys
+    assert b"2" in values and [50] in values and b"9" not in values
+    assert (b"a", b"1") in items and (b"a", b"2") not in items and (b"z", b"1") not in items
+
+    assert keys & {b"a", b"z"} == {b"a"}
+    assert [b"z"] | keys == {b"a", b"b", b"c", b"z"}
+    assert keys - [b"a"] == {b"b", b"c"}
+    assert {b"a", b"z"} - keys == {b"z"}
+    assert keys ^ {b"a", b"z"} == {b"b", b"c", b"z"}
+    assert keys == {b"a", b"b", b"c"} and keys < {b"a", b"b", b"c", b"d"}
+    assert keys.isdisjoint([b"z"])
+
+    # raw values take part in set operations as bytes
+    assert items & {(b"a", b"1"), (b"b", b"9")} == {(b"a", b"1")}
+    assert items | {(b"z", b"9")} == {(b"a", b"1"), (b"b", b"2"), (b"c", b"3"), (b"z", b"9")}
+    assert items - {(b"a", b"1")} == {(b"b", b"2"), (b"c", b"3")}
+    assert {(b"a", b"1"), (b"z", b"9")} - items == {(b"z", b"9")}
+    assert items ^ {(b"a", b"1"), (b"z", b"9")} == {(b"b", b"2"), (b"c", b"3"), (b"z", b"9")}
+    assert items == {(b"a", b"1"), (b"b", b"2"), (b"c", b"3")}
+    assert items != {(b"a", b"9"), (b"b", b"2"), (b"c", b"3")}
+    assert items > {(b"a", b"1")}
+    assert items.isdisjoint({(b"a", b"9")})
+
+    # views follow later changes
+    db.insert(b"d", b"4")
+    assert len(keys) == 4 and b"d" in keys
+
+
+def test_typed_views():
+    db = pysled.SledDb.in_memory()
+    tree = db.open_tree(b"typed", key_codec="str", value_codec="json")
+    tree.update({"x": [1], "y": {"a": 2}})
+    assert list(tree.keys()) == ["x", "y"]
+    assert {"a": 2} in tree.values()
+    assert ("x", [1]) in tree.items()
+    assert tree.keys() & {"x", "z"} == {"x"}
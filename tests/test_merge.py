@@ -0,0 +1,67 @@
+import struct
+import sys
+
+import pytest
+
+import pysled
+
+
+def _u64(n):
+    return struct.pack(">Q", n)
+
+
+def _set(*items):
+    return b"".join(struct.pack(">I", len(item)) + item for item in items)
+
+
+def test_u64_add():
+    db = pysled.SledDb.in_memory()
+    db.set_merge_operator("u64_add")
+    db.merge(b"n", _u64(2))
+    assert bytes(db.merge(b"n", _u64(40))) == _u64(42)
+    assert bytes(db.merge(b"n", _u64(2**64 - 42))) == _u64(0)
+    # operands that are not 8 bytes leave the value alone
+    assert bytes(db.merge(b"n", b"x")) == _u64(0)
+
+
+def test_set_union_wire_format():
+    db = pysled.SledDb.in_memory()
+    db.set_merge_operator("set_union")
+    db.merge(b"s", _set(b"b", b"a"))
+    assert bytes(db.merge(b"s", _set(b"c", b"a", b""))) == _set(b"", b"a", b"b", b"c")
+    assert bytes(db.merge(b"s", b"\x00\x00\x00\x09a")) == _set(b"", b"a", b"b", b"c")
+
+
+def test_max_and_min():
+    db = pysled.SledDb.in_memory()
+    high, low = db.open_tree(b"high"), db.open_tree(b"low")
+    high.set_merge_operator("max")
+    low.set_merge_operator("min")
+    for value in [b"b", b"c", b"a"]:
+        high.merge(b"k", value)
+        low.merge(b"k", value)
+    assert bytes(high[b"k"]) == b"c" and bytes(low[b"k"]) == b"a"
+
+
+def test_unknown_operator():
+    db = pysled.SledDb.in_memory()
+    with pytest.raises(ValueError, match="Unknown merge operator"):
+        db.set_merge_operator("sum")
+
+
+def test_python_operator_that_raises_keeps_the_value():
+    db = pysled.SledDb.in_memory()
+    db.insert(b"k", b"old")
+
+    def fail(key, old, new):
+        raise RuntimeError("merge failed")
+
+    db.set_merge_operator(fail)
+    reported = []
+    hook, sys.unraisablehook = sys.unraisablehook, reported.append
+    try:
+        db.merge(b"k", b"new")
+    finally:
+        sys.unraisablehook = hook
+    assert bytes(db[b"k"]) == b"old"
+    assert [str(r.exc_value) for r in reported] == ["merge failed"]
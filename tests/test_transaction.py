@@ -0,0 +1,59 @@
+import pytest
+
+import pysled
+
+
+class _Oops(Exception):
+    pass
+
+
+def test_user_exception_is_reraised_unchanged():
+    db = pysled.SledDb.in_memory()
+    error = _Oops("from the callback")
+
+    def f(tree):
+        tree.insert(b"k", b"v")
+        raise error
+
+    with pytest.raises(_Oops) as e:
+        db.transaction(f)
+    assert e.value is error
+
+
+def test_abort_rolls_back():
+    db = pysled.SledDb.in_memory()
+    db.insert(b"kept", b"1")
+
+    def f(tree):
+        tree.insert(b"new", b"1")
+        tree.remove(b"kept")
+        raise _Oops()
+
+    with pytest.raises(_Oops):
+        db.transaction(f)
+    assert dict(db) == {b"kept": [49]}
+
+
+def test_commit_spans_trees():
+    db = pysled.SledDb.in_memory()
+    a, b = db.open_tree(b"a"), db.open_tree(b"b")
+    a.insert(b"balance", b"10")
+
+    def move(ta, tb, tdb):
+        tb.insert(b"balance", ta.remove(b"balance"))
+        tdb.insert(b"moved", b"1")
+        return "done"
+
+    assert pysled.transaction([a, b, db], move) == "done"
+    assert dict(a) == {} and dict(b) == {b"balance": list(b"10")} and dict(db) == {b"moved": [49]}
+
+
+def test_trees_of_different_databases_are_unsupported():
+    first, second = pysled.SledDb.in_memory(), pysled.SledDb.in_memory()
+    with pytest.raises(pysled.Unsupported):
+        pysled.transaction([first, second], lambda a, b: None)
+
+
+def test_needs_a_tree():
+    with pytest.raises(ValueError, match="at least one tree"):
+        pysled.transaction([], lambda: None)
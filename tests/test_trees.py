@@ -0,0 +1,21 @@
+import pysled
+
+
+def test_tree_names():
+    db = pysled.SledDb.in_memory()
+    db.insert(b"k", b"v")
+    db.open_tree(b"t").insert(b"a", b"1")
+    assert db.tree_names() == [b"__sled__default", b"t"]
+    assert list(db.iter_tree_names()) == [b"__sled__default", b"t"]
+    assert [tree.name for tree in db.trees()] == [b"__sled__default", b"t"]
+    # the database itself iterates like a mapping of its default tree
+    assert list(db) == [b"k"]
+
+
+def test_tree_stats_and_recovery(tmp_path):
+    db = pysled.SledDb(str(tmp_path / "db"))
+    assert not db.was_recovered()
+    db.open_tree(b"t").update({b"a": b"1", b"b": b"2"})
+    assert db.tree_stats() == {b"__sled__default": 0, b"t": 2}
+    db.close()
+    assert pysled.SledDb(str(tmp_path / "db")).was_recovered()